@@ -0,0 +1,42 @@
+// Writes the voxel grid used by scenes/cloud.json: an ellipsoid of density roughened by noise.
+//
+//     cargo run --release --example cloud_grid -- scenes/cloud.grid
+use ray_tracing_in_one_weekend::{Perlin, Vec3, VoxelGrid};
+
+const WIDTH: usize = 48;
+const HEIGHT: usize = 32;
+const DEPTH: usize = 48;
+
+fn main() -> std::io::Result<()> {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| String::from("cloud.grid"));
+    let perlin = Perlin::new(7);
+
+    let mut values = Vec::with_capacity(WIDTH * HEIGHT * DEPTH);
+    for z in 0..DEPTH {
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let point = Vec3::new(
+                    (x as f64 + 0.5) / WIDTH as f64,
+                    (y as f64 + 0.5) / HEIGHT as f64,
+                    (z as f64 + 0.5) / DEPTH as f64,
+                );
+
+                // 1 at the center of the ellipsoid falling to 0 at its surface, flatter below
+                let offset = point - Vec3::new(0.5, 0.4, 0.5);
+                let below = if offset.y() < 0.0 { 2.0 } else { 1.0 };
+                let shape = 1.0
+                    - (offset.x() / 0.42).powi(2)
+                    - (below * offset.y() / 0.38).powi(2)
+                    - (offset.z() / 0.42).powi(2);
+
+                let noise = perlin.fbm(&(4.0 * point), 5);
+                values.push((2.0 * (shape + 0.6 * noise)).clamp(0.0, 1.0) as f32);
+            }
+        }
+    }
+
+    let grid = VoxelGrid::new(WIDTH, HEIGHT, DEPTH, values).expect("one value per voxel");
+    grid.write(std::io::BufWriter::new(std::fs::File::create(path)?))
+}
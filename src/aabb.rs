@@ -0,0 +1,141 @@
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+#[derive(Clone, Copy)]
+pub struct Aabb {
+    minimum: Vec3,
+    maximum: Vec3,
+}
+
+impl Aabb {
+    pub fn new(minimum: Vec3, maximum: Vec3) -> Self {
+        Self { minimum, maximum }
+    }
+
+    pub fn minimum(&self) -> &Vec3 {
+        &self.minimum
+    }
+
+    pub fn maximum(&self) -> &Vec3 {
+        &self.maximum
+    }
+
+    pub fn from_points(points: &[Vec3]) -> Self {
+        let padding = 1e-4;
+
+        let mut minimum = points[0];
+        let mut maximum = points[0];
+        for point in &points[1..] {
+            minimum = Vec3::new(
+                minimum.x().min(point.x()),
+                minimum.y().min(point.y()),
+                minimum.z().min(point.z()),
+            );
+            maximum = Vec3::new(
+                maximum.x().max(point.x()),
+                maximum.y().max(point.y()),
+                maximum.z().max(point.z()),
+            );
+        }
+
+        let pad = |minimum: f64, maximum: f64| {
+            if maximum - minimum < padding {
+                (minimum - padding / 2.0, maximum + padding / 2.0)
+            } else {
+                (minimum, maximum)
+            }
+        };
+        let (min_x, max_x) = pad(minimum.x(), maximum.x());
+        let (min_y, max_y) = pad(minimum.y(), maximum.y());
+        let (min_z, max_z) = pad(minimum.z(), maximum.z());
+
+        Self {
+            minimum: Vec3::new(min_x, min_y, min_z),
+            maximum: Vec3::new(max_x, max_y, max_z),
+        }
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        0.5 * (self.minimum + self.maximum)
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let extent = self.maximum - self.minimum;
+        2.0 * (extent.x() * extent.y() + extent.y() * extent.z() + extent.z() * extent.x())
+    }
+
+    pub fn surrounding(&self, other: &Aabb) -> Aabb {
+        Self {
+            minimum: Vec3::new(
+                self.minimum.x().min(other.minimum.x()),
+                self.minimum.y().min(other.minimum.y()),
+                self.minimum.z().min(other.minimum.z()),
+            ),
+            maximum: Vec3::new(
+                self.maximum.x().max(other.maximum.x()),
+                self.maximum.y().max(other.maximum.y()),
+                self.maximum.z().max(other.maximum.z()),
+            ),
+        }
+    }
+
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+
+        for axis in 0..3 {
+            let inverse_direction = 1.0 / ray.direction()[axis];
+            let mut t0 = (self.minimum[axis] - ray.origin()[axis]) * inverse_direction;
+            let mut t1 = (self.maximum[axis] - ray.origin()[axis]) * inverse_direction;
+            if inverse_direction < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max <= t_min {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn rays_parallel_to_a_slab_hit_only_from_inside_it() {
+        let direction = Vec3::new(1.0, 0.0, 0.0);
+        let inside = Ray::new(Vec3::new(-1.0, 0.5, 0.5), direction, 0.0);
+        let outside = Ray::new(Vec3::new(-1.0, 1.5, 0.5), direction, 0.0);
+        assert!(unit_box().hit(&inside, 0.001, f64::INFINITY));
+        assert!(!unit_box().hit(&outside, 0.001, f64::INFINITY));
+
+        let negative_zero = Ray::new(Vec3::new(-1.0, 0.5, 0.5), Vec3::new(1.0, -0.0, -0.0), 0.0);
+        assert!(unit_box().hit(&negative_zero, 0.001, f64::INFINITY));
+    }
+
+    #[test]
+    fn rays_in_the_plane_of_a_face_are_not_culled() {
+        // 0 * infinity makes the slab NaN, which must not reject the box
+        for &(y, z) in &[(0.0, 0.5), (1.0, 0.5), (0.0, 0.0), (1.0, 1.0)] {
+            let ray = Ray::new(Vec3::new(-1.0, y, z), Vec3::new(1.0, 0.0, 0.0), 0.0);
+            assert!(unit_box().hit(&ray, 0.001, f64::INFINITY));
+        }
+    }
+
+    #[test]
+    fn axis_aligned_rays_respect_the_interval() {
+        let ray = Ray::new(Vec3::new(0.5, 0.5, -2.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert!(unit_box().hit(&ray, 0.001, 2.5));
+        assert!(!unit_box().hit(&ray, 0.001, 1.5));
+        assert!(!unit_box().hit(&ray, 3.5, f64::INFINITY));
+    }
+}
@@ -0,0 +1,29 @@
+use crate::color::Color;
+use crate::ray::Ray;
+
+pub enum Background {
+    Solid(Color),
+    Gradient { bottom: Color, top: Color },
+}
+
+impl Background {
+    pub fn color(&self, ray: &Ray) -> Color {
+        match self {
+            Background::Solid(color) => color.clone(),
+            Background::Gradient { bottom, top } => {
+                let unit_direction = ray.direction().unit_vector();
+                let t = 0.5 * (unit_direction.y() + 1.0);
+                (1.0 - t) * bottom + t * top
+            }
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
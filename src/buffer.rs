@@ -1,6 +1,8 @@
 use crate::color::Color;
-use crate::Pixel;
+use crate::pixel::Pixel;
+use crate::tile::Tile;
 
+#[derive(Clone)]
 pub struct Buffer {
     buffer: Vec<Color>,
     height: usize,
@@ -24,6 +26,14 @@ impl Buffer {
         }
     }
 
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
     pub fn get(&self, pixel: &Pixel) -> &Color {
         &self.buffer[self.width * pixel.j() + pixel.i()]
     }
@@ -31,4 +41,10 @@ impl Buffer {
     pub fn set(&mut self, pixel: &Pixel, color: Color) {
         self.buffer[self.width * pixel.j() + pixel.i()] = color;
     }
+
+    pub fn set_tile(&mut self, tile: &Tile, colors: &Buffer) {
+        for (local, pixel) in tile.pixels() {
+            self.set(&pixel, colors.get(&local).clone());
+        }
+    }
 }
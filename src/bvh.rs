@@ -0,0 +1,183 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::hittable_list::HittableList;
+use crate::ray::Ray;
+
+pub struct BvhNode {
+    left: std::sync::Arc<dyn Hittable>,
+    right: std::sync::Arc<dyn Hittable>,
+    bounding_box: Aabb,
+}
+
+impl BvhNode {
+    pub fn new(objects: Vec<std::sync::Arc<dyn Hittable>>) -> Self {
+        assert!(!objects.is_empty(), "cannot build a bvh from an empty list");
+
+        let objects = objects
+            .into_iter()
+            .map(|object| {
+                let bounding_box = object
+                    .bounding_box()
+                    .expect("cannot build a bvh from an unbounded object");
+                (bounding_box, object)
+            })
+            .collect();
+
+        Self::build(objects)
+    }
+
+    pub fn from_list(list: &HittableList) -> Self {
+        Self::new(list.objects().to_vec())
+    }
+
+    // Objects without a bounding box, such as planes, cannot be placed in a bvh, so they are kept
+    // in a list next to a bvh of the remaining objects.
+    pub fn with_unbounded(list: &HittableList) -> HittableList {
+        let (bounded, unbounded): (Vec<_>, Vec<_>) = list
+            .objects()
+            .iter()
+            .cloned()
+            .partition(|object| object.bounding_box().is_some());
+
+        let mut world = HittableList::new();
+        if !bounded.is_empty() {
+            world.add(std::sync::Arc::new(Self::new(bounded)));
+        }
+        for object in unbounded {
+            world.add(object);
+        }
+        world
+    }
+
+    fn build(mut objects: Vec<(Aabb, std::sync::Arc<dyn Hittable>)>) -> Self {
+        if objects.len() == 1 {
+            let (bounding_box, object) = objects.pop().unwrap();
+            return Self {
+                left: object.clone(),
+                right: object,
+                bounding_box,
+            };
+        }
+
+        let (axis, split) = Self::surface_area_heuristic(&mut objects);
+        objects.sort_by(|(a, _), (b, _)| a.centroid()[axis].total_cmp(&b.centroid()[axis]));
+        let right = objects.split_off(split);
+        let left = objects;
+
+        let (left_box, left) = Self::child(left);
+        let (right_box, right) = Self::child(right);
+
+        Self {
+            left,
+            right,
+            bounding_box: left_box.surrounding(&right_box),
+        }
+    }
+
+    fn child(
+        mut objects: Vec<(Aabb, std::sync::Arc<dyn Hittable>)>,
+    ) -> (Aabb, std::sync::Arc<dyn Hittable>) {
+        if objects.len() == 1 {
+            objects.pop().unwrap()
+        } else {
+            let node = Self::build(objects);
+            (node.bounding_box, std::sync::Arc::new(node))
+        }
+    }
+
+    fn surface_area_heuristic(
+        objects: &mut [(Aabb, std::sync::Arc<dyn Hittable>)],
+    ) -> (usize, usize) {
+        let count = objects.len();
+        let mut best = (0, count / 2, f64::INFINITY);
+
+        for axis in 0..3 {
+            objects.sort_by(|(a, _), (b, _)| a.centroid()[axis].total_cmp(&b.centroid()[axis]));
+
+            let mut right_areas = vec![0.0; count];
+            let mut right_box = objects[count - 1].0;
+            for i in (1..count).rev() {
+                right_box = right_box.surrounding(&objects[i].0);
+                right_areas[i] = right_box.surface_area();
+            }
+
+            let mut left_box = objects[0].0;
+            for split in 1..count {
+                left_box = left_box.surrounding(&objects[split - 1].0);
+                let cost = left_box.surface_area() * split as f64
+                    + right_areas[split] * (count - split) as f64;
+                if cost < best.2 {
+                    best = (axis, split, cost);
+                }
+            }
+        }
+
+        (best.0, best.1)
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        if !self.bounding_box.hit(ray, t_min, t_max) {
+            return None;
+        }
+
+        let hit_left = self.left.hit(ray, t_min, t_max);
+        let t_max = hit_left.as_ref().map_or(t_max, |h| h.t());
+        let hit_right = self.right.hit(ray, t_min, t_max);
+
+        hit_right.or(hit_left)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bounding_box)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::hittable::{Sphere, Triangle};
+    use crate::material::Lambertian;
+    use crate::number::Pcg32;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn bvh_hits_the_same_objects_as_a_list() {
+        let mut rng = Pcg32::new(1, 0);
+        let material = std::sync::Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+
+        let mut list = HittableList::new();
+        for _ in 0..100 {
+            let center = Vec3::random(Some((-5.0, 5.0)), &mut rng);
+            list.add(std::sync::Arc::new(Sphere::new(
+                center,
+                rng.random_f64(Some((0.1, 1.0))),
+                material.clone(),
+            )));
+            list.add(std::sync::Arc::new(Triangle::new(
+                center + Vec3::random(Some((-1.0, 1.0)), &mut rng),
+                center + Vec3::random(Some((-1.0, 1.0)), &mut rng),
+                center + Vec3::random(Some((-1.0, 1.0)), &mut rng),
+                material.clone(),
+            )));
+        }
+        let bvh = BvhNode::from_list(&list);
+
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let ray = Ray::new(
+                Vec3::random(Some((-8.0, 8.0)), &mut rng),
+                Vec3::random_unit_vector(&mut rng),
+                0.0,
+            );
+            let expected = list.hit(&ray, 0.001, f64::INFINITY).map(|hit| hit.t());
+            let actual = bvh.hit(&ray, 0.001, f64::INFINITY).map(|hit| hit.t());
+            assert_eq!(actual, expected);
+            hits += expected.is_some() as usize;
+        }
+        // the rays start among the objects, so both hits and misses are compared
+        assert!(hits > 100 && hits < 1900, "{} hits", hits);
+    }
+}
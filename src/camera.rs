@@ -1,3 +1,4 @@
+use crate::number::Pcg32;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 
@@ -10,6 +11,8 @@ pub struct Camera {
     v: Vec3,
     w: Vec3,
     lens_radius: f64,
+    shutter_open: f64,
+    shutter_close: f64,
 }
 
 impl Camera {
@@ -47,16 +50,57 @@ impl Camera {
             v,
             w,
             lens_radius,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
         }
     }
 
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let random_disk = self.lens_radius * Vec3::random_in_unit_disk();
+    pub fn with_shutter(mut self, shutter_open: f64, shutter_close: f64) -> Self {
+        self.shutter_open = shutter_open;
+        self.shutter_close = shutter_close;
+        self
+    }
+
+    pub fn with_aspect_ratio(mut self, aspect_ratio: f64) -> Self {
+        let center = self.lower_left_corner + self.horizontal / 2.0 + self.vertical / 2.0;
+        let horizontal_length = self.vertical.length() * aspect_ratio;
+
+        self.horizontal = horizontal_length * self.horizontal.unit_vector();
+        self.lower_left_corner = center - self.horizontal / 2.0 - self.vertical / 2.0;
+        self
+    }
+
+    pub fn origin(&self) -> &Vec3 {
+        &self.origin
+    }
+
+    // The camera's orthonormal basis: right, up, and backwards from the view direction.
+    pub fn u(&self) -> &Vec3 {
+        &self.u
+    }
+
+    pub fn v(&self) -> &Vec3 {
+        &self.v
+    }
+
+    pub fn w(&self) -> &Vec3 {
+        &self.w
+    }
+
+    pub fn get_ray(&self, s: f64, t: f64, rng: &mut Pcg32) -> Ray {
+        let random_disk = self.lens_radius * Vec3::random_in_unit_disk(rng);
         let offset = self.u * random_disk.x() + self.v * random_disk.y();
 
+        let time = if self.shutter_open < self.shutter_close {
+            rng.random_f64(Some((self.shutter_open, self.shutter_close)))
+        } else {
+            self.shutter_open
+        };
+
         Ray::new(
             self.origin + offset,
             self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
+            time,
         )
     }
 }
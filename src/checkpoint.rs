@@ -0,0 +1,162 @@
+use crate::color::Color;
+use crate::film::Film;
+use crate::pixel::Pixel;
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 8] = b"RTWCKPT2";
+// the red, green and blue sums, the sum of squared luminances and the count of a pixel
+const PIXEL_SIZE: usize = 5 * 8;
+// far more samples than a render takes, but few enough that adding a pass cannot overflow
+const MAX_COUNT: u64 = u32::MAX as u64;
+
+// Saves a film with the seed it was rendered with. Every sample draws from a generator seeded by
+// the seed, its pixel and its index, and a pixel's next index is its sample count, so this is the
+// complete random number state needed to continue the render. The scene's fingerprint lets a
+// resumed render check that it continues the same scene.
+pub fn write_checkpoint<W: Write>(
+    mut writer: W,
+    seed: u64,
+    fingerprint: u64,
+    film: &Film,
+) -> std::io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&seed.to_le_bytes())?;
+    writer.write_all(&fingerprint.to_le_bytes())?;
+    writer.write_all(&(film.width() as u64).to_le_bytes())?;
+    writer.write_all(&(film.height() as u64).to_le_bytes())?;
+
+    for j in 0..film.height() {
+        for i in 0..film.width() {
+            let pixel = Pixel::new(i, j);
+            let sum = film.sum(&pixel);
+            for value in [sum.r(), sum.g(), sum.b(), film.squared_luminance(&pixel)] {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            writer.write_all(&(film.count(&pixel) as u64).to_le_bytes())?;
+        }
+    }
+
+    writer.flush()
+}
+
+// Reads the seed, scene fingerprint and film of a checkpoint.
+pub fn read_checkpoint<R: Read>(mut reader: R) -> std::io::Result<(u64, u64, Film)> {
+    let invalid =
+        |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string());
+
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a render checkpoint"));
+    }
+
+    let mut read_u64 = || -> std::io::Result<u64> {
+        let mut bytes = [0; 8];
+        reader.read_exact(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    };
+
+    let seed = read_u64()?;
+    let fingerprint = read_u64()?;
+    let width = read_u64()? as usize;
+    let height = read_u64()? as usize;
+    let size = width
+        .checked_mul(height)
+        .and_then(|count| count.checked_mul(PIXEL_SIZE))
+        .filter(|&size| size > 0)
+        .ok_or_else(|| invalid("checkpoint has an invalid size"))?;
+
+    // the pixels are read before the film is allocated, so a corrupt header cannot make it huge
+    let mut bytes = Vec::new();
+    reader.take(size as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != size {
+        return Err(invalid("checkpoint is truncated"));
+    }
+
+    let mut film = Film::new(width, height);
+    for (index, pixel) in bytes.chunks_exact(PIXEL_SIZE).enumerate() {
+        let mut values = pixel.chunks_exact(8).map(|value| {
+            u64::from_le_bytes([
+                value[0], value[1], value[2], value[3], value[4], value[5], value[6], value[7],
+            ])
+        });
+        let mut next = || values.next().unwrap();
+        let r = f64::from_bits(next());
+        let g = f64::from_bits(next());
+        let b = f64::from_bits(next());
+        let squared_luminance = f64::from_bits(next());
+        let count = next();
+        if count > MAX_COUNT {
+            return Err(invalid("checkpoint has an invalid sample count"));
+        }
+        film.set(
+            &Pixel::new(index % width, index / width),
+            Color::new(r, g, b),
+            squared_luminance,
+            count as usize,
+        );
+    }
+
+    Ok((seed, fingerprint, film))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn film() -> Film {
+        let mut film = Film::new(3, 2);
+        for j in 0..2 {
+            for i in 0..3 {
+                let pixel = Pixel::new(i, j);
+                for sample in 0..i + j {
+                    film.add(&pixel, Color::new(0.1 * sample as f64, 0.5, i as f64));
+                }
+            }
+        }
+        film
+    }
+
+    fn checkpoint(film: &Film) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_checkpoint(&mut bytes, 7, 11, film).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn checkpoints_round_trip() {
+        let film = film();
+        let (seed, fingerprint, read) = read_checkpoint(&checkpoint(&film)[..]).unwrap();
+        assert_eq!((seed, fingerprint), (7, 11));
+        assert_eq!((read.width(), read.height()), (3, 2));
+        for j in 0..2 {
+            for i in 0..3 {
+                let pixel = Pixel::new(i, j);
+                assert_eq!(read.count(&pixel), film.count(&pixel));
+                assert_eq!(read.sum(&pixel).r(), film.sum(&pixel).r());
+                assert_eq!(read.sum(&pixel).b(), film.sum(&pixel).b());
+                assert_eq!(
+                    read.squared_luminance(&pixel),
+                    film.squared_luminance(&pixel)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_checkpoints_are_rejected() {
+        let bytes = checkpoint(&film());
+        let error = |bytes: &[u8]| read_checkpoint(bytes).err().unwrap().to_string();
+
+        assert_eq!(error(&bytes[..bytes.len() - 1]), "checkpoint is truncated");
+
+        let mut magic = bytes.clone();
+        magic[0] = b'X';
+        assert_eq!(error(&magic), "not a render checkpoint");
+
+        // the count of the first pixel follows the magic, seed, fingerprint, size and its sums
+        let mut count = bytes;
+        count[40 + 32..40 + 40].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(error(&count), "checkpoint has an invalid sample count");
+    }
+}
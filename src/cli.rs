@@ -0,0 +1,301 @@
+use clap::{App, Arg};
+use ray_tracing_in_one_weekend::{ImageFormat, RenderSettings};
+use std::path::{Path, PathBuf};
+
+pub struct Options {
+    scene: Option<PathBuf>,
+    width: Option<usize>,
+    height: Option<usize>,
+    samples_per_pixel: Option<usize>,
+    max_depth: Option<usize>,
+    min_samples_per_pixel: Option<usize>,
+    noise_threshold: Option<f64>,
+    pass_samples: Option<usize>,
+    threads: usize,
+    tile_size: usize,
+    seed: u64,
+    output: Option<PathBuf>,
+    format: ImageFormat,
+    heatmap: Option<(PathBuf, ImageFormat)>,
+    checkpoint: Option<PathBuf>,
+    resume: Option<PathBuf>,
+}
+
+impl Options {
+    pub fn parse() -> Self {
+        let matches = App::new("ray-tracing-in-one-weekend")
+            .version(clap::crate_version!())
+            .about("Renders a scene with a parallel path tracer")
+            .arg(
+                Arg::with_name("scene")
+                    .value_name("SCENE")
+                    .help("Scene description file; renders the random spheres scene if omitted"),
+            )
+            .arg(
+                Arg::with_name("width")
+                    .long("width")
+                    .value_name("PIXELS")
+                    .validator(image_size)
+                    .help("Image width, overriding the scene"),
+            )
+            .arg(
+                Arg::with_name("height")
+                    .long("height")
+                    .value_name("PIXELS")
+                    .validator(image_size)
+                    .help("Image height, overriding the scene"),
+            )
+            .arg(
+                Arg::with_name("samples")
+                    .long("samples")
+                    .short("s")
+                    .value_name("COUNT")
+                    .validator(positive_integer)
+                    .help("Samples per pixel, overriding the scene"),
+            )
+            .arg(
+                Arg::with_name("depth")
+                    .long("depth")
+                    .short("d")
+                    .value_name("BOUNCES")
+                    .validator(positive_integer)
+                    .help("Maximum ray bounces, overriding the scene"),
+            )
+            .arg(
+                Arg::with_name("min-samples")
+                    .long("min-samples")
+                    .value_name("COUNT")
+                    .validator(positive_integer)
+                    .help(
+                        "Samples per pixel before adaptive sampling may stop, overriding the scene",
+                    ),
+            )
+            .arg(
+                Arg::with_name("noise-threshold")
+                    .long("noise-threshold")
+                    .value_name("ERROR")
+                    .validator(positive_number)
+                    .help(
+                        "Stop sampling a pixel once the relative standard error of its mean \
+                         falls below this, overriding the scene",
+                    ),
+            )
+            .arg(
+                Arg::with_name("pass-samples")
+                    .long("pass-samples")
+                    .value_name("COUNT")
+                    .validator(positive_integer)
+                    .help(
+                        "Render progressively in passes of this many samples per pixel, \
+                         rewriting the output image and checkpoint after each pass",
+                    ),
+            )
+            .arg(
+                Arg::with_name("threads")
+                    .long("threads")
+                    .short("t")
+                    .value_name("COUNT")
+                    .validator(positive_integer)
+                    .help("Worker threads [default: number of cpus]"),
+            )
+            .arg(
+                Arg::with_name("tile-size")
+                    .long("tile-size")
+                    .value_name("PIXELS")
+                    .validator(positive_integer)
+                    .default_value("16")
+                    .help("Width and height of the square tiles handed to each thread"),
+            )
+            .arg(
+                Arg::with_name("seed")
+                    .long("seed")
+                    .value_name("SEED")
+                    .validator(|value| {
+                        value
+                            .parse::<u64>()
+                            .map(|_| ())
+                            .map_err(|_| format!("'{}' is not a valid seed", value))
+                    })
+                    .default_value("0")
+                    .help("Seed for the random numbers used to build and render the scene"),
+            )
+            .arg(
+                Arg::with_name("output")
+                    .long("output")
+                    .short("o")
+                    .value_name("FILE")
+                    .help("Output image file [default: stdout]"),
+            )
+            .arg(
+                Arg::with_name("format")
+                    .long("format")
+                    .short("f")
+                    .value_name("FORMAT")
+                    .possible_values(ImageFormat::NAMES)
+                    .help("Output image format [default: from the output extension, else ppm]"),
+            )
+            .arg(
+                Arg::with_name("checkpoint")
+                    .long("checkpoint")
+                    .value_name("FILE")
+                    .help(
+                        "Save the accumulated samples after each pass [default pass: 16 samples]",
+                    ),
+            )
+            .arg(
+                Arg::with_name("resume")
+                    .long("resume")
+                    .value_name("FILE")
+                    .conflicts_with("seed")
+                    .help("Continue the render saved in a checkpoint, with its seed"),
+            )
+            .arg(
+                Arg::with_name("heatmap")
+                    .long("heatmap")
+                    .value_name("FILE")
+                    .help("Also write an image of the samples taken per pixel"),
+            )
+            .get_matches();
+
+        let output = matches.value_of_os("output").map(PathBuf::from);
+        let format = match (matches.value_of("format"), output.as_ref()) {
+            (Some(name), _) => ImageFormat::from_name(name).unwrap(),
+            (None, Some(path)) => format_of(path, "use --format"),
+            (None, None) => ImageFormat::Ppm,
+        };
+        let heatmap = matches.value_of_os("heatmap").map(|path| {
+            let path = PathBuf::from(path);
+            let format = format_of(&path, "use a .ppm, .png, .exr, .hdr or .pfm file");
+            (path, format)
+        });
+
+        let usize_of = |name| matches.value_of(name).map(|value| value.parse().unwrap());
+
+        Self {
+            scene: matches.value_of_os("scene").map(PathBuf::from),
+            width: usize_of("width"),
+            height: usize_of("height"),
+            samples_per_pixel: usize_of("samples"),
+            max_depth: usize_of("depth"),
+            min_samples_per_pixel: usize_of("min-samples"),
+            noise_threshold: matches
+                .value_of("noise-threshold")
+                .map(|value| value.parse().unwrap()),
+            pass_samples: usize_of("pass-samples"),
+            threads: usize_of("threads").unwrap_or_else(num_cpus::get),
+            tile_size: usize_of("tile-size").unwrap(),
+            seed: matches.value_of("seed").unwrap().parse().unwrap(),
+            output,
+            format,
+            heatmap,
+            checkpoint: matches.value_of_os("checkpoint").map(PathBuf::from),
+            resume: matches.value_of_os("resume").map(PathBuf::from),
+        }
+    }
+
+    pub fn scene(&self) -> Option<&PathBuf> {
+        self.scene.as_ref()
+    }
+
+    pub fn pass_samples(&self) -> Option<usize> {
+        self.pass_samples
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn tile_size(&self) -> usize {
+        self.tile_size
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn output(&self) -> Option<&PathBuf> {
+        self.output.as_ref()
+    }
+
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    pub fn heatmap(&self) -> Option<&(PathBuf, ImageFormat)> {
+        self.heatmap.as_ref()
+    }
+
+    pub fn checkpoint(&self) -> Option<&PathBuf> {
+        self.checkpoint.as_ref()
+    }
+
+    pub fn resume(&self) -> Option<&PathBuf> {
+        self.resume.as_ref()
+    }
+
+    pub fn render_settings(&self, scene: &RenderSettings) -> RenderSettings {
+        let (width, height) = match (self.width, self.height) {
+            (Some(width), Some(height)) => (width, height),
+            (Some(width), None) => (
+                width,
+                ((width as f64 / scene.aspect_ratio()).round() as usize).max(1),
+            ),
+            (None, Some(height)) => (
+                ((height as f64 * scene.aspect_ratio()).round() as usize).max(1),
+                height,
+            ),
+            (None, None) => (scene.width(), scene.height()),
+        };
+
+        RenderSettings::new(
+            width,
+            height,
+            self.samples_per_pixel
+                .unwrap_or_else(|| scene.samples_per_pixel()),
+            self.max_depth.unwrap_or_else(|| scene.max_depth()),
+        )
+        .with_adaptive_sampling(
+            self.min_samples_per_pixel
+                .unwrap_or_else(|| scene.min_samples_per_pixel()),
+            self.noise_threshold.or_else(|| scene.noise_threshold()),
+        )
+    }
+}
+
+fn positive_integer(value: String) -> Result<(), String> {
+    match value.parse::<usize>() {
+        Ok(number) if number > 0 => Ok(()),
+        _ => Err(format!("'{}' is not a positive integer", value)),
+    }
+}
+
+fn image_size(value: String) -> Result<(), String> {
+    match value.parse::<usize>() {
+        Ok(size) if size >= 2 => Ok(()),
+        _ => Err(format!(
+            "'{}' is not an image size of at least 2 pixels",
+            value
+        )),
+    }
+}
+
+fn positive_number(value: String) -> Result<(), String> {
+    match value.parse::<f64>() {
+        Ok(number) if number > 0.0 => Ok(()),
+        _ => Err(format!("'{}' is not a positive number", value)),
+    }
+}
+
+fn format_of(path: &Path, hint: &str) -> ImageFormat {
+    ImageFormat::from_extension(path).unwrap_or_else(|| {
+        clap::Error::with_description(
+            &format!(
+                "cannot infer the image format of '{}', {}",
+                path.display(),
+                hint
+            ),
+            clap::ErrorKind::InvalidValue,
+        )
+        .exit()
+    })
+}
@@ -1,4 +1,4 @@
-use crate::number::random_f64;
+use crate::number::Pcg32;
 use crate::vec3::Vec3;
 use std::fmt::{Display, Formatter};
 
@@ -14,6 +14,22 @@ impl Color {
         Self { r, g, b }
     }
 
+    pub fn r(&self) -> f64 {
+        self.r
+    }
+
+    pub fn g(&self) -> f64 {
+        self.g
+    }
+
+    pub fn b(&self) -> f64 {
+        self.b
+    }
+
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
     pub fn sampled(&self, samples: usize) -> Color {
         let scale = 1.0 / samples as f64;
         Self {
@@ -23,29 +39,38 @@ impl Color {
         }
     }
 
-    pub fn gamma(&self) -> Color {
+    pub fn srgb(&self) -> Color {
+        fn encode(linear: f64) -> f64 {
+            if linear <= 0.0031308 {
+                12.92 * linear
+            } else {
+                1.055 * linear.powf(1.0 / 2.4) - 0.055
+            }
+        }
+
         Self {
-            r: self.r.sqrt(),
-            g: self.g.sqrt(),
-            b: self.b.sqrt(),
+            r: encode(self.r),
+            g: encode(self.g),
+            b: encode(self.b),
         }
     }
 
-    pub fn random(min_max: Option<(f64, f64)>) -> Color {
+    pub fn random(min_max: Option<(f64, f64)>, rng: &mut Pcg32) -> Color {
         let min_max = Some(min_max.unwrap_or((0.0, 1.0)));
         Self {
-            r: random_f64(min_max),
-            g: random_f64(min_max),
-            b: random_f64(min_max),
+            r: rng.random_f64(min_max),
+            g: rng.random_f64(min_max),
+            b: rng.random_f64(min_max),
         }
     }
 }
 
 impl Display for Color {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let r = (256.0 * self.r.clamp(0.0, 0.999)) as u8;
-        let g = (256.0 * self.g.clamp(0.0, 0.999)) as u8;
-        let b = (256.0 * self.b.clamp(0.0, 0.999)) as u8;
+        // quantized the same way as 8-bit PNG
+        let r = (255.0 * self.r.clamp(0.0, 1.0)).round() as u8;
+        let g = (255.0 * self.g.clamp(0.0, 1.0)).round() as u8;
+        let b = (255.0 * self.b.clamp(0.0, 1.0)).round() as u8;
 
         write!(f, "{} {} {}", r, g, b)
     }
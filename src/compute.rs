@@ -75,7 +75,6 @@ impl<T> Compute<T> {
     where
         F: FnOnce(A, &T, R) -> A,
         F: Copy,
-        R: Clone,
     {
         let mut processed = 0;
         let total = jobs.len();
@@ -90,11 +89,12 @@ impl<T> Compute<T> {
                 .unwrap();
 
             let id = result.id();
-            accumulator = function(accumulator, result.task(), result.result().clone());
+            let (task, result) = result.into_parts();
+            accumulator = function(accumulator, &task, result);
 
             if processed < total {
-                if let Some(pixel) = jobs.pop() {
-                    self.compute(id, pixel);
+                if let Some(job) = jobs.pop() {
+                    self.compute(id, job);
                 }
             } else {
                 return accumulator;
@@ -130,12 +130,8 @@ impl<T, R> ComputeResult<T, R> {
         self.id
     }
 
-    pub fn task(&self) -> &T {
-        &self.task
-    }
-
-    pub fn result(&self) -> &R {
-        &self.result
+    pub fn into_parts(self) -> (T, R) {
+        (self.task, self.result)
     }
 }
 
@@ -0,0 +1,135 @@
+use crate::buffer::Buffer;
+use crate::color::Color;
+use crate::pixel::Pixel;
+use crate::tile::Tile;
+
+#[derive(Clone)]
+pub struct Film {
+    sums: Buffer,
+    squares: Vec<f64>,
+    counts: Vec<usize>,
+}
+
+impl Film {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            sums: Buffer::new(width, height),
+            squares: vec![0.0; width * height],
+            counts: vec![0; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.sums.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.sums.height()
+    }
+
+    pub fn sum(&self, pixel: &Pixel) -> &Color {
+        self.sums.get(pixel)
+    }
+
+    pub fn squared_luminance(&self, pixel: &Pixel) -> f64 {
+        self.squares[self.index(pixel)]
+    }
+
+    pub fn count(&self, pixel: &Pixel) -> usize {
+        self.counts[self.index(pixel)]
+    }
+
+    pub fn set(&mut self, pixel: &Pixel, sum: Color, squared_luminance: f64, count: usize) {
+        let index = self.index(pixel);
+        self.sums.set(pixel, sum);
+        self.squares[index] = squared_luminance;
+        self.counts[index] = count;
+    }
+
+    pub fn add(&mut self, pixel: &Pixel, color: Color) {
+        let index = self.index(pixel);
+        let luminance = color.luminance();
+        self.sums.set(pixel, self.sums.get(pixel) + &color);
+        self.squares[index] += luminance.powi(2);
+        self.counts[index] += 1;
+    }
+
+    pub fn mean(&self, pixel: &Pixel) -> Color {
+        match self.count(pixel) {
+            0 => Color::new(0.0, 0.0, 0.0),
+            count => self.sum(pixel).sampled(count),
+        }
+    }
+
+    // Standard error of the mean luminance relative to the mean itself, so that the same
+    // threshold applies to dark and bright pixels.
+    pub fn relative_error(&self, pixel: &Pixel) -> f64 {
+        let count = self.count(pixel);
+        if count < 2 {
+            return f64::INFINITY;
+        }
+
+        let mean = self.mean(pixel).luminance();
+        let variance = (self.squares[self.index(pixel)] / count as f64 - mean.powi(2)).max(0.0)
+            * count as f64
+            / (count - 1) as f64;
+        (variance / count as f64).sqrt() / mean.max(1e-3)
+    }
+
+    pub fn tile(&self, tile: &Tile) -> Film {
+        let mut film = Film::new(tile.width(), tile.height());
+        for (local, pixel) in tile.pixels() {
+            film.set(
+                &local,
+                self.sum(&pixel).clone(),
+                self.squared_luminance(&pixel),
+                self.count(&pixel),
+            );
+        }
+        film
+    }
+
+    pub fn set_tile(&mut self, tile: &Tile, film: &Film) {
+        for (local, pixel) in tile.pixels() {
+            self.set(
+                &pixel,
+                film.sum(&local).clone(),
+                film.squared_luminance(&local),
+                film.count(&local),
+            );
+        }
+    }
+
+    pub fn to_buffer(&self) -> Buffer {
+        let mut buffer = Buffer::new(self.width(), self.height());
+        for j in 0..self.height() {
+            for i in 0..self.width() {
+                let pixel = Pixel::new(i, j);
+                buffer.set(&pixel, self.mean(&pixel));
+            }
+        }
+        buffer
+    }
+
+    // Sample counts mapped from blue (fewest) through green to red (`max_samples`).
+    pub fn heatmap(&self, max_samples: usize) -> Buffer {
+        let mut buffer = Buffer::new(self.width(), self.height());
+        for j in 0..self.height() {
+            for i in 0..self.width() {
+                let pixel = Pixel::new(i, j);
+                let x = (self.count(&pixel) as f64 / max_samples.max(1) as f64).clamp(0.0, 1.0);
+                let color = Color::new(
+                    (2.0 * x - 1.0).max(0.0),
+                    1.0 - (2.0 * x - 1.0).abs(),
+                    (1.0 - 2.0 * x).max(0.0),
+                );
+                buffer.set(&pixel, color);
+            }
+        }
+        buffer
+    }
+
+    fn index(&self, pixel: &Pixel) -> usize {
+        self.width() * pixel.j() + pixel.i()
+    }
+}
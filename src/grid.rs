@@ -0,0 +1,235 @@
+use crate::vec3::Vec3;
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 8] = b"RTWGRID1";
+
+// A dense grid of densities sampled at voxel centers. Files start with a magic number and the
+// width, height and depth as u64 little endian, followed by one f32 little endian per voxel with x
+// varying fastest and z slowest.
+pub struct VoxelGrid {
+    width: usize,
+    height: usize,
+    depth: usize,
+    values: Vec<f32>,
+    maximum: f64,
+}
+
+impl VoxelGrid {
+    // None unless there is one value for each of a nonzero number of voxels.
+    pub fn new(width: usize, height: usize, depth: usize, values: Vec<f32>) -> Option<Self> {
+        let count = width.checked_mul(height)?.checked_mul(depth)?;
+        if count == 0 || values.len() != count {
+            return None;
+        }
+
+        let maximum = values
+            .iter()
+            .fold(0.0f32, |maximum, &value| maximum.max(value)) as f64;
+        Some(Self {
+            width,
+            height,
+            depth,
+            values,
+            maximum,
+        })
+    }
+
+    pub fn load(path: &std::path::Path) -> std::io::Result<Self> {
+        Self::read(std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> std::io::Result<Self> {
+        let invalid = |message: &str| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+        };
+
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a voxel grid"));
+        }
+
+        let mut read_u64 = || -> std::io::Result<usize> {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            Ok(u64::from_le_bytes(bytes) as usize)
+        };
+        let width = read_u64()?;
+        let height = read_u64()?;
+        let depth = read_u64()?;
+        let count = width
+            .checked_mul(height)
+            .and_then(|count| count.checked_mul(depth))
+            .filter(|&count| count > 0 && count.checked_mul(4).is_some())
+            .ok_or_else(|| invalid("voxel grid has an invalid size"))?;
+
+        let mut bytes = Vec::new();
+        reader.take(4 * count as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != 4 * count {
+            return Err(invalid("voxel grid is truncated"));
+        }
+
+        let values = bytes
+            .chunks_exact(4)
+            .map(|value| f32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+            .collect::<Vec<_>>();
+        if values
+            .iter()
+            .any(|value| !value.is_finite() || *value < 0.0)
+        {
+            return Err(invalid(
+                "voxel grid densities must be finite and non-negative",
+            ));
+        }
+
+        Self::new(width, height, depth, values)
+            .ok_or_else(|| invalid("voxel grid has an invalid size"))
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writer.write_all(MAGIC)?;
+        for size in [self.width, self.height, self.depth] {
+            writer.write_all(&(size as u64).to_le_bytes())?;
+        }
+        for value in &self.values {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn maximum(&self) -> f64 {
+        self.maximum
+    }
+
+    // Trilinearly interpolated density at a point in the unit cube spanned by the grid, clamping to
+    // the outermost voxels near the faces.
+    pub fn density(&self, point: &Vec3) -> f64 {
+        let axis = |coordinate: f64, size: usize| {
+            let x = (coordinate * size as f64 - 0.5).clamp(0.0, (size - 1) as f64);
+            let i = (x.floor() as usize).min(size.saturating_sub(2));
+            (i, (i + 1).min(size - 1), x - i as f64)
+        };
+        let (x0, x1, fx) = axis(point.x(), self.width);
+        let (y0, y1, fy) = axis(point.y(), self.height);
+        let (z0, z1, fz) = axis(point.z(), self.depth);
+
+        let value = |x: usize, y: usize, z: usize| {
+            self.values[(z * self.height + y) * self.width + x] as f64
+        };
+        let lerp = |a: f64, b: f64, t: f64| a + t * (b - a);
+
+        lerp(
+            lerp(
+                lerp(value(x0, y0, z0), value(x1, y0, z0), fx),
+                lerp(value(x0, y1, z0), value(x1, y1, z0), fx),
+                fy,
+            ),
+            lerp(
+                lerp(value(x0, y0, z1), value(x1, y0, z1), fx),
+                lerp(value(x0, y1, z1), value(x1, y1, z1), fx),
+                fy,
+            ),
+            fz,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> VoxelGrid {
+        VoxelGrid::new(2, 1, 3, vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.5]).unwrap()
+    }
+
+    fn bytes(grid: &VoxelGrid) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        grid.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn header(width: u64, height: u64, depth: u64) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        for size in [width, height, depth] {
+            bytes.extend_from_slice(&size.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn assert_invalid(bytes: &[u8]) {
+        match VoxelGrid::read(bytes) {
+            Err(error) => assert_eq!(error.kind(), std::io::ErrorKind::InvalidData),
+            Ok(_) => panic!("invalid grid was read"),
+        }
+    }
+
+    #[test]
+    fn new_rejects_mismatched_and_empty_grids() {
+        assert!(VoxelGrid::new(2, 2, 2, vec![0.0; 7]).is_none());
+        assert!(VoxelGrid::new(0, 2, 2, Vec::new()).is_none());
+        assert!(VoxelGrid::new(usize::MAX, 2, 2, Vec::new()).is_none());
+    }
+
+    #[test]
+    fn round_trip() {
+        let grid = VoxelGrid::read(&bytes(&grid())[..]).unwrap();
+        assert_eq!((grid.width(), grid.height(), grid.depth()), (2, 1, 3));
+        assert_eq!(grid.values, [0.0, 1.0, 2.0, 3.0, 4.0, 5.5]);
+        assert_eq!(grid.maximum(), 5.5);
+    }
+
+    #[test]
+    fn rejects_truncated_grids() {
+        let bytes = bytes(&grid());
+        match VoxelGrid::read(&bytes[..bytes.len() - 1]) {
+            Err(error) => assert_eq!(error.to_string(), "voxel grid is truncated"),
+            Ok(_) => panic!("truncated grid was read"),
+        }
+        // the header itself is cut short
+        let error = VoxelGrid::read(&bytes[..20]).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        let mut bytes = bytes(&grid());
+        bytes[0] = b'X';
+        assert_invalid(&bytes);
+
+        assert_invalid(&header(0, 1, 1));
+        assert_invalid(&header(u64::MAX, u64::MAX, 2));
+        assert_invalid(&header(1 << 62, 1, 1));
+    }
+
+    #[test]
+    fn rejects_invalid_densities() {
+        for value in [-1.0f32, f32::NAN, f32::INFINITY] {
+            let mut bytes = header(1, 1, 1);
+            bytes.extend_from_slice(&value.to_le_bytes());
+            assert_invalid(&bytes);
+        }
+    }
+
+    #[test]
+    fn density_interpolates_between_voxel_centers() {
+        let grid = grid();
+        // voxel centers along x are at 0.25 and 0.75, along z at 1/6, 1/2 and 5/6
+        assert_eq!(grid.density(&Vec3::new(0.25, 0.5, 0.5)), 2.0);
+        assert_eq!(grid.density(&Vec3::new(0.5, 0.5, 0.5)), 2.5);
+        // clamped to the outermost voxels
+        assert_eq!(grid.density(&Vec3::new(0.0, 0.0, 0.0)), 0.0);
+        assert_eq!(grid.density(&Vec3::new(1.0, 1.0, 1.0)), 5.5);
+    }
+}
@@ -1,4 +1,9 @@
+use crate::aabb::Aabb;
+use crate::hittable_list::HittableList;
 use crate::material::Material;
+use crate::matrix::Transform;
+use crate::number::Pcg32;
+use crate::onb::Onb;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 
@@ -6,16 +11,18 @@ pub struct HitRecord {
     front_face: bool,
     normal: Vec3,
     point: Vec3,
-    time: f64,
+    t: f64,
+    u: f64,
+    v: f64,
     material: std::sync::Arc<dyn Material>,
 }
 
 impl HitRecord {
-    fn new(
+    pub fn new(
         ray: &Ray,
         outward_normal: Vec3,
         point: Vec3,
-        time: f64,
+        t: f64,
         material: std::sync::Arc<dyn Material>,
     ) -> Self {
         let front_face = ray.direction().dot(&outward_normal) < 0.0;
@@ -29,11 +36,19 @@ impl HitRecord {
             front_face,
             normal,
             point,
-            time,
+            t,
+            u: 0.0,
+            v: 0.0,
             material,
         }
     }
 
+    pub fn with_uv(mut self, u: f64, v: f64) -> Self {
+        self.u = u;
+        self.v = v;
+        self
+    }
+
     pub fn normal(&self) -> &Vec3 {
         &self.normal
     }
@@ -42,8 +57,16 @@ impl HitRecord {
         &self.point
     }
 
-    pub fn time(&self) -> f64 {
-        self.time
+    pub fn t(&self) -> f64 {
+        self.t
+    }
+
+    pub fn u(&self) -> f64 {
+        self.u
+    }
+
+    pub fn v(&self) -> f64 {
+        self.v
     }
 
     pub fn front_face(&self) -> bool {
@@ -56,7 +79,19 @@ impl HitRecord {
 }
 
 pub trait Hittable: Send + Sync {
-    fn hit(&self, ray: &Ray, time_min: f64, time_max: f64) -> Option<HitRecord>;
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+
+    fn bounding_box(&self) -> Option<Aabb>;
+
+    // Objects that can be sampled as lights return the solid angle density of `direction` as seen
+    // from `origin`, and generate directions from `origin` towards themselves with that density.
+    fn pdf_value(&self, _origin: &Vec3, _direction: &Vec3) -> f64 {
+        0.0
+    }
+
+    fn random(&self, _origin: &Vec3, _rng: &mut Pcg32) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
 }
 
 pub struct Sphere {
@@ -73,39 +108,920 @@ impl Sphere {
             material,
         }
     }
+
+    fn uv(point: &Vec3) -> (f64, f64) {
+        let theta = (-point.y()).acos();
+        let phi = (-point.z()).atan2(point.x()) + std::f64::consts::PI;
+
+        (
+            phi / (2.0 * std::f64::consts::PI),
+            theta / std::f64::consts::PI,
+        )
+    }
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, time_min: f64, time_max: f64) -> Option<HitRecord> {
-        let oc = ray.origin() - self.center;
-        let a = ray.direction().length_squared();
-        let half_b = oc.dot(ray.direction());
-        let c = oc.length_squared() - self.radius.powi(2);
-
-        let discriminant = half_b.powi(2) - a * c;
-        if discriminant < 0.0 {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        hit_sphere(self.center, self.radius, &self.material, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let radius = self.radius.abs();
+        let radius = Vec3::new(radius, radius, radius);
+        Some(Aabb::new(self.center - radius, self.center + radius))
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let distance_squared = (self.center - origin).length_squared();
+        if distance_squared <= self.radius.powi(2)
+            || self
+                .hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::INFINITY)
+                .is_none()
+        {
+            return 0.0;
+        }
+
+        let cos_theta_max = (1.0 - self.radius.powi(2) / distance_squared).sqrt();
+        let solid_angle = 2.0 * std::f64::consts::PI * (1.0 - cos_theta_max);
+        1.0 / solid_angle
+    }
+
+    fn random(&self, origin: &Vec3, rng: &mut Pcg32) -> Vec3 {
+        let direction = self.center - origin;
+        let distance_squared = direction.length_squared();
+        let cos_theta_max = (1.0 - self.radius.powi(2) / distance_squared)
+            .max(0.0)
+            .sqrt();
+
+        let r1 = rng.random_f64(None);
+        let r2 = rng.random_f64(None);
+        let z = 1.0 + r2 * (cos_theta_max - 1.0);
+        let phi = 2.0 * std::f64::consts::PI * r1;
+        let sin_theta = (1.0 - z.powi(2)).sqrt();
+
+        Onb::new(&direction).local(phi.cos() * sin_theta, phi.sin() * sin_theta, z)
+    }
+}
+
+fn hit_sphere(
+    center: Vec3,
+    radius: f64,
+    material: &std::sync::Arc<dyn Material>,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord> {
+    let oc = ray.origin() - center;
+    let a = ray.direction().length_squared();
+    let half_b = oc.dot(ray.direction());
+    let c = oc.length_squared() - radius.powi(2);
+
+    let discriminant = half_b.powi(2) - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let square_root_of_discriminant = discriminant.sqrt();
+
+    let mut root = (-half_b - square_root_of_discriminant) / a;
+    if root < t_min || t_max < root {
+        root = (-half_b + square_root_of_discriminant) / a;
+        if root < t_min || t_max < root {
             return None;
         }
-        let square_root_of_discriminant = discriminant.sqrt();
+    }
+
+    let t = root;
+    let point = ray.at(t);
+    let normal = (point - center) / radius;
+    let (u, v) = Sphere::uv(&((point - center) / radius.abs()));
+
+    Some(HitRecord::new(ray, normal, point, t, material.clone()).with_uv(u, v))
+}
+
+pub struct MovingSphere {
+    center_0: Vec3,
+    center_1: Vec3,
+    time_0: f64,
+    time_1: f64,
+    radius: f64,
+    material: std::sync::Arc<dyn Material>,
+}
+
+impl MovingSphere {
+    pub fn new(
+        center_0: Vec3,
+        center_1: Vec3,
+        time_0: f64,
+        time_1: f64,
+        radius: f64,
+        material: std::sync::Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            center_0,
+            center_1,
+            time_0,
+            time_1,
+            radius,
+            material,
+        }
+    }
+
+    // The sphere rests at its end centers outside of its motion, which keeps it inside its
+    // bounding box for any shutter.
+    pub fn center(&self, time: f64) -> Vec3 {
+        let t = ((time - self.time_0) / (self.time_1 - self.time_0)).clamp(0.0, 1.0);
+        self.center_0 + t * (self.center_1 - self.center_0)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        hit_sphere(
+            self.center(ray.time()),
+            self.radius,
+            &self.material,
+            ray,
+            t_min,
+            t_max,
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let radius = self.radius.abs();
+        let radius = Vec3::new(radius, radius, radius);
+        let box_0 = Aabb::new(self.center_0 - radius, self.center_0 + radius);
+        let box_1 = Aabb::new(self.center_1 - radius, self.center_1 + radius);
+        Some(box_0.surrounding(&box_1))
+    }
+}
+
+pub struct Moving {
+    object: std::sync::Arc<dyn Hittable>,
+    offset_0: Vec3,
+    offset_1: Vec3,
+    time_0: f64,
+    time_1: f64,
+}
+
+impl Moving {
+    pub fn new(
+        object: std::sync::Arc<dyn Hittable>,
+        offset_0: Vec3,
+        offset_1: Vec3,
+        time_0: f64,
+        time_1: f64,
+    ) -> Self {
+        Self {
+            object,
+            offset_0,
+            offset_1,
+            time_0,
+            time_1,
+        }
+    }
+
+    // Like `MovingSphere::center`, the offset stops changing outside of the motion.
+    pub fn offset(&self, time: f64) -> Vec3 {
+        let t = ((time - self.time_0) / (self.time_1 - self.time_0)).clamp(0.0, 1.0);
+        self.offset_0 + t * (self.offset_1 - self.offset_0)
+    }
+}
+
+impl Hittable for Moving {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let offset = self.offset(ray.time());
+        let moved = Ray::new(ray.origin() - offset, *ray.direction(), ray.time());
+
+        let mut hit_record = self.object.hit(&moved, t_min, t_max)?;
+        hit_record.point = hit_record.point + offset;
+        Some(hit_record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let bounding_box = self.object.bounding_box()?;
+        let box_0 = Aabb::new(
+            *bounding_box.minimum() + self.offset_0,
+            *bounding_box.maximum() + self.offset_0,
+        );
+        let box_1 = Aabb::new(
+            *bounding_box.minimum() + self.offset_1,
+            *bounding_box.maximum() + self.offset_1,
+        );
+        Some(box_0.surrounding(&box_1))
+    }
+}
+
+pub struct Transformed {
+    object: std::sync::Arc<dyn Hittable>,
+    transform: Transform,
+}
+
+impl Transformed {
+    pub fn new(object: std::sync::Arc<dyn Hittable>, transform: Transform) -> Self {
+        Self { object, transform }
+    }
+}
+
+impl Hittable for Transformed {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let to_object = self.transform.inverse();
+        let object_ray = Ray::new(
+            to_object.point(ray.origin()),
+            to_object.vector(ray.direction()),
+            ray.time(),
+        );
 
-        let root = (-half_b - square_root_of_discriminant) / a;
-        if root < time_min || time_max < root {
-            let root = (-half_b - square_root_of_discriminant) / a;
-            if root < time_min || time_max < root {
-                return None;
+        let mut hit_record = self.object.hit(&object_ray, t_min, t_max)?;
+        hit_record.point = self.transform.point(&hit_record.point);
+        hit_record.normal = self.transform.normal(&hit_record.normal).unit_vector();
+        Some(hit_record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let bounding_box = self.object.bounding_box()?;
+        let (minimum, maximum) = (bounding_box.minimum(), bounding_box.maximum());
+
+        let mut corners = Vec::with_capacity(8);
+        for &x in &[minimum.x(), maximum.x()] {
+            for &y in &[minimum.y(), maximum.y()] {
+                for &z in &[minimum.z(), maximum.z()] {
+                    corners.push(self.transform.point(&Vec3::new(x, y, z)));
+                }
             }
         }
 
-        let time = root;
-        let point = ray.at(time);
-        let normal = (point - self.center) / self.radius;
+        Some(Aabb::from_points(&corners))
+    }
+}
 
-        Some(HitRecord::new(
+pub struct Triangle {
+    vertices: [Vec3; 3],
+    normals: Option<[Vec3; 3]>,
+    uvs: Option<[(f64, f64); 3]>,
+    material: std::sync::Arc<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(v0: Vec3, v1: Vec3, v2: Vec3, material: std::sync::Arc<dyn Material>) -> Self {
+        Self {
+            vertices: [v0, v1, v2],
+            normals: None,
+            uvs: None,
+            material,
+        }
+    }
+
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: [(f64, f64); 3]) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t, barycentric) = intersect_triangle(ray, &self.vertices, t_min, t_max)?;
+
+        Some(triangle_hit_record(
             ray,
-            normal,
-            point,
-            time,
+            t,
+            barycentric,
+            &self.vertices,
+            self.normals.as_ref(),
+            self.uvs.as_ref(),
             self.material.clone(),
         ))
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::from_points(&self.vertices))
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let ray = Ray::new(*origin, *direction, 0.0);
+        let (t, _) = match intersect_triangle(&ray, &self.vertices, 0.001, f64::INFINITY) {
+            Some(hit) => hit,
+            None => return 0.0,
+        };
+
+        let normal =
+            (self.vertices[1] - self.vertices[0]).cross(&(self.vertices[2] - self.vertices[0]));
+        let area = normal.length() / 2.0;
+        let distance_squared = t.powi(2) * direction.length_squared();
+        let cosine = (direction.dot(&normal) / (direction.length() * normal.length())).abs();
+        if cosine <= 0.0 {
+            return 0.0;
+        }
+
+        distance_squared / (cosine * area)
+    }
+
+    fn random(&self, origin: &Vec3, rng: &mut Pcg32) -> Vec3 {
+        let square_root = rng.random_f64(None).sqrt();
+        let b0 = 1.0 - square_root;
+        let b1 = rng.random_f64(None) * square_root;
+        let point =
+            b0 * self.vertices[0] + b1 * self.vertices[1] + (1.0 - b0 - b1) * self.vertices[2];
+        point - origin
+    }
+}
+
+// Watertight ray/triangle intersection (Woop, Benthin and Wald, 2013). Edges shared between
+// triangles are evaluated identically from either side, so meshes have no cracks.
+pub fn intersect_triangle(
+    ray: &Ray,
+    vertices: &[Vec3; 3],
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, [f64; 3])> {
+    let direction = ray.direction();
+
+    let kz = (0..3)
+        .max_by(|&a, &b| direction[a].abs().total_cmp(&direction[b].abs()))
+        .unwrap();
+    let mut kx = (kz + 1) % 3;
+    let mut ky = (kx + 1) % 3;
+    if direction[kz] < 0.0 {
+        std::mem::swap(&mut kx, &mut ky);
+    }
+
+    let shear_x = direction[kx] / direction[kz];
+    let shear_y = direction[ky] / direction[kz];
+    let shear_z = 1.0 / direction[kz];
+
+    let a = vertices[0] - ray.origin();
+    let b = vertices[1] - ray.origin();
+    let c = vertices[2] - ray.origin();
+
+    let ax = a[kx] - shear_x * a[kz];
+    let ay = a[ky] - shear_y * a[kz];
+    let bx = b[kx] - shear_x * b[kz];
+    let by = b[ky] - shear_y * b[kz];
+    let cx = c[kx] - shear_x * c[kz];
+    let cy = c[ky] - shear_y * c[kz];
+
+    let u = cx * by - cy * bx;
+    let v = ax * cy - ay * cx;
+    let w = bx * ay - by * ax;
+
+    if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) {
+        return None;
+    }
+
+    // A ray through an edge lies on it in both triangles sharing the edge, which traverse it in
+    // opposite directions if they are wound the same way. Only the triangle whose edge points
+    // up, or right if it is level, keeps the hit, so a ray through a mesh hits it once.
+    let owns = |dx: f64, dy: f64| dy > 0.0 || (dy == 0.0 && dx > 0.0);
+    if (u == 0.0 && !owns(cx - bx, cy - by))
+        || (v == 0.0 && !owns(ax - cx, ay - cy))
+        || (w == 0.0 && !owns(bx - ax, by - ay))
+    {
+        return None;
+    }
+
+    let determinant = u + v + w;
+    if determinant == 0.0 {
+        return None;
+    }
+
+    let az = shear_z * a[kz];
+    let bz = shear_z * b[kz];
+    let cz = shear_z * c[kz];
+
+    let t = (u * az + v * bz + w * cz) / determinant;
+    if t < t_min || t_max < t {
+        return None;
+    }
+
+    Some((t, [u / determinant, v / determinant, w / determinant]))
+}
+
+pub fn triangle_hit_record(
+    ray: &Ray,
+    t: f64,
+    barycentric: [f64; 3],
+    vertices: &[Vec3; 3],
+    normals: Option<&[Vec3; 3]>,
+    uvs: Option<&[(f64, f64); 3]>,
+    material: std::sync::Arc<dyn Material>,
+) -> HitRecord {
+    let [b0, b1, b2] = barycentric;
+
+    let geometric_normal = (vertices[1] - vertices[0])
+        .cross(&(vertices[2] - vertices[0]))
+        .unit_vector();
+
+    let outward_normal = match normals {
+        Some(normals) => {
+            let normal = (b0 * normals[0] + b1 * normals[1] + b2 * normals[2]).unit_vector();
+            if normal.dot(&geometric_normal) < 0.0 {
+                -normal
+            } else {
+                normal
+            }
+        }
+        None => geometric_normal,
+    };
+
+    let (u, v) = match uvs {
+        Some(uvs) => (
+            b0 * uvs[0].0 + b1 * uvs[1].0 + b2 * uvs[2].0,
+            b0 * uvs[0].1 + b1 * uvs[1].1 + b2 * uvs[2].1,
+        ),
+        None => (b1, b2),
+    };
+
+    HitRecord::new(ray, outward_normal, ray.at(t), t, material).with_uv(u, v)
+}
+
+pub struct Quad {
+    corner: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    normal: Vec3,
+    distance: f64,
+    area: f64,
+    material: std::sync::Arc<dyn Material>,
+}
+
+impl Quad {
+    pub fn new(corner: Vec3, u: Vec3, v: Vec3, material: std::sync::Arc<dyn Material>) -> Self {
+        let n = u.cross(&v);
+        let normal = n.unit_vector();
+
+        Self {
+            corner,
+            u,
+            v,
+            w: n / n.length_squared(),
+            normal,
+            distance: normal.dot(&corner),
+            area: n.length(),
+            material,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let denominator = self.normal.dot(ray.direction());
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.distance - self.normal.dot(ray.origin())) / denominator;
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let planar = point - self.corner;
+        let alpha = self.w.dot(&planar.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        Some(HitRecord::new(ray, self.normal, point, t, self.material.clone()).with_uv(alpha, beta))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::from_points(&[
+            self.corner,
+            self.corner + self.u,
+            self.corner + self.v,
+            self.corner + self.u + self.v,
+        ]))
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let hit_record = match self.hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::INFINITY) {
+            Some(hit_record) => hit_record,
+            None => return 0.0,
+        };
+
+        let distance_squared = hit_record.t().powi(2) * direction.length_squared();
+        let cosine = (direction.dot(&self.normal) / direction.length()).abs();
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random(&self, origin: &Vec3, rng: &mut Pcg32) -> Vec3 {
+        let point = self.corner + rng.random_f64(None) * self.u + rng.random_f64(None) * self.v;
+        point - origin
+    }
+}
+
+// An infinite plane through `point` facing along `normal`. It has no bounding box, and its texture
+// coordinates are distances from `point` along two perpendicular directions in the plane.
+pub struct Plane {
+    point: Vec3,
+    normal: Vec3,
+    onb: Onb,
+    material: std::sync::Arc<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, material: std::sync::Arc<dyn Material>) -> Self {
+        Self {
+            point,
+            normal: normal.unit_vector(),
+            onb: Onb::new(&normal),
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let denominator = self.normal.dot(ray.direction());
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.point - ray.origin()).dot(&self.normal) / denominator;
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let planar = point - self.point;
+        let u = planar.dot(self.onb.u());
+        let v = planar.dot(self.onb.v());
+
+        Some(HitRecord::new(ray, self.normal, point, t, self.material.clone()).with_uv(u, v))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub fn index(&self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+
+    // The two axes spanning the plane perpendicular to this one, in x, y, z order.
+    pub fn others(&self) -> (Axis, Axis) {
+        match self {
+            Axis::X => (Axis::Y, Axis::Z),
+            Axis::Y => (Axis::X, Axis::Z),
+            Axis::Z => (Axis::X, Axis::Y),
+        }
+    }
+
+    fn vector(&self, along: f64, a: f64, b: f64) -> Vec3 {
+        match self {
+            Axis::X => Vec3::new(along, a, b),
+            Axis::Y => Vec3::new(a, along, b),
+            Axis::Z => Vec3::new(a, b, along),
+        }
+    }
+}
+
+// A rectangle perpendicular to `axis` at `k`, spanning `a` and `b` along the other two axes in
+// x, y, z order. Its normal points along the positive axis.
+pub struct Rectangle {
+    axis: Axis,
+    a: (f64, f64),
+    b: (f64, f64),
+    k: f64,
+    material: std::sync::Arc<dyn Material>,
+}
+
+impl Rectangle {
+    pub fn new(
+        axis: Axis,
+        a: (f64, f64),
+        b: (f64, f64),
+        k: f64,
+        material: std::sync::Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            axis,
+            a: (a.0.min(a.1), a.0.max(a.1)),
+            b: (b.0.min(b.1), b.0.max(b.1)),
+            k,
+            material,
+        }
+    }
+
+    fn area(&self) -> f64 {
+        (self.a.1 - self.a.0) * (self.b.1 - self.b.0)
+    }
+}
+
+impl Hittable for Rectangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (a_axis, b_axis) = self.axis.others();
+        let (origin, direction) = (ray.origin(), ray.direction());
+
+        let t = (self.k - origin[self.axis.index()]) / direction[self.axis.index()];
+        if !(t_min..=t_max).contains(&t) {
+            return None;
+        }
+
+        let a = origin[a_axis.index()] + t * direction[a_axis.index()];
+        let b = origin[b_axis.index()] + t * direction[b_axis.index()];
+        if a < self.a.0 || self.a.1 < a || b < self.b.0 || self.b.1 < b {
+            return None;
+        }
+
+        let u = (a - self.a.0) / (self.a.1 - self.a.0);
+        let v = (b - self.b.0) / (self.b.1 - self.b.0);
+        let outward_normal = self.axis.vector(1.0, 0.0, 0.0);
+
+        Some(HitRecord::new(ray, outward_normal, ray.at(t), t, self.material.clone()).with_uv(u, v))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::from_points(&[
+            self.axis.vector(self.k, self.a.0, self.b.0),
+            self.axis.vector(self.k, self.a.1, self.b.1),
+        ]))
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let hit_record = match self.hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::INFINITY) {
+            Some(hit_record) => hit_record,
+            None => return 0.0,
+        };
+
+        let distance_squared = hit_record.t().powi(2) * direction.length_squared();
+        let cosine = (direction[self.axis.index()] / direction.length()).abs();
+        distance_squared / (cosine * self.area())
+    }
+
+    fn random(&self, origin: &Vec3, rng: &mut Pcg32) -> Vec3 {
+        let point = self.axis.vector(
+            self.k,
+            rng.random_f64(Some(self.a)),
+            rng.random_f64(Some(self.b)),
+        );
+        point - origin
+    }
+}
+
+// An axis-aligned box made of six quads whose normals face outwards.
+pub struct Cuboid {
+    minimum: Vec3,
+    maximum: Vec3,
+    sides: HittableList,
+}
+
+impl Cuboid {
+    pub fn new(a: Vec3, b: Vec3, material: std::sync::Arc<dyn Material>) -> Self {
+        let minimum = Vec3::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()));
+        let maximum = Vec3::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()));
+
+        let dx = Vec3::new(maximum.x() - minimum.x(), 0.0, 0.0);
+        let dy = Vec3::new(0.0, maximum.y() - minimum.y(), 0.0);
+        let dz = Vec3::new(0.0, 0.0, maximum.z() - minimum.z());
+
+        let (x0, y0, z0) = (minimum.x(), minimum.y(), minimum.z());
+        let (x1, y1, z1) = (maximum.x(), maximum.y(), maximum.z());
+
+        let mut sides = HittableList::new();
+        for (corner, u, v) in [
+            (Vec3::new(x0, y0, z1), dx, dy),
+            (Vec3::new(x1, y0, z1), -dz, dy),
+            (Vec3::new(x1, y0, z0), -dx, dy),
+            (Vec3::new(x0, y0, z0), dz, dy),
+            (Vec3::new(x0, y1, z1), dx, -dz),
+            (Vec3::new(x0, y0, z0), dx, dz),
+        ] {
+            sides.add(std::sync::Arc::new(Quad::new(
+                corner,
+                u,
+                v,
+                material.clone(),
+            )));
+        }
+
+        Self {
+            minimum,
+            maximum,
+            sides,
+        }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.sides.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::from_points(&[self.minimum, self.maximum]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+
+    fn unit_sphere() -> Sphere {
+        Sphere::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            1.0,
+            std::sync::Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn sphere_hit_from_outside_returns_the_near_root() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, -3.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let hit_record = unit_sphere().hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((hit_record.t() - 2.0).abs() < 1e-12);
+        assert!(hit_record.front_face());
+    }
+
+    #[test]
+    fn sphere_hit_from_inside_returns_the_far_root() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.5), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let hit_record = unit_sphere().hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((hit_record.t() - 0.5).abs() < 1e-12);
+        assert!((hit_record.point().z() - 1.0).abs() < 1e-12);
+        assert!(!hit_record.front_face());
+        // the normal faces the ray, so it points back towards the center
+        assert!(hit_record.normal().z() < 0.0);
+    }
+
+    #[test]
+    fn sphere_hit_respects_the_interval() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, -3.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let hit_record = unit_sphere().hit(&ray, 2.5, f64::INFINITY).unwrap();
+        assert!((hit_record.t() - 4.0).abs() < 1e-12);
+        assert!(unit_sphere().hit(&ray, 0.001, 1.5).is_none());
+    }
+
+    #[test]
+    fn moving_objects_rest_outside_of_their_motion() {
+        let material = std::sync::Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let start = Vec3::new(0.0, 0.0, 0.0);
+        let end = Vec3::new(2.0, 0.0, 0.0);
+        let sphere = MovingSphere::new(start, end, 0.0, 1.0, 1.0, material);
+        let moving = Moving::new(std::sync::Arc::new(unit_sphere()), start, end, 0.0, 1.0);
+
+        assert_eq!(sphere.center(-1.0).x(), 0.0);
+        assert_eq!(sphere.center(3.0).x(), 2.0);
+        assert_eq!(moving.offset(-1.0).x(), 0.0);
+        assert_eq!(moving.offset(3.0).x(), 2.0);
+
+        // a ray at a time after the motion still hits inside the bounding box
+        let ray = Ray::new(Vec3::new(2.0, 0.0, -3.0), Vec3::new(0.0, 0.0, 1.0), 3.0);
+        for object in [&sphere as &dyn Hittable, &moving] {
+            let hit_record = object.hit(&ray, 0.001, f64::INFINITY).unwrap();
+            assert!((hit_record.t() - 2.0).abs() < 1e-12);
+            assert!(object
+                .bounding_box()
+                .unwrap()
+                .hit(&ray, 0.001, f64::INFINITY));
+        }
+    }
+
+    fn triangle(a: (f64, f64, f64), b: (f64, f64, f64), c: (f64, f64, f64)) -> Triangle {
+        let vertex = |(x, y, z)| Vec3::new(x, y, z);
+        Triangle::new(
+            vertex(a),
+            vertex(b),
+            vertex(c),
+            std::sync::Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    // The number of `triangles` hit by a ray from `origin` through `target`.
+    fn hits(triangles: &[Triangle], origin: Vec3, target: Vec3) -> usize {
+        let ray = Ray::new(origin, target - origin, 0.0);
+        triangles
+            .iter()
+            .filter(|triangle| triangle.hit(&ray, 0.001, f64::INFINITY).is_some())
+            .count()
+    }
+
+    #[test]
+    fn rays_through_a_shared_edge_hit_one_triangle() {
+        let triangles = [
+            triangle((0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)),
+            triangle((1.0, 0.0, 0.0), (1.0, 1.0, 0.0), (0.0, 1.0, 0.0)),
+        ];
+        for step in 1..10 {
+            let s = step as f64 / 10.0;
+            let target = Vec3::new(1.0 - s, s, 0.0);
+            for offset in &[
+                Vec3::new(0.0, 0.0, 1.0),
+                Vec3::new(0.0, 0.0, -1.0),
+                Vec3::new(0.3, -0.2, 1.0),
+                Vec3::new(-0.7, 0.1, -2.0),
+            ] {
+                assert_eq!(hits(&triangles, target + *offset, target), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn rays_through_a_shared_vertex_hit_one_triangle() {
+        let fan = [
+            triangle((0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)),
+            triangle((0.0, 0.0, 0.0), (0.0, 1.0, 0.0), (-1.0, 0.0, 0.0)),
+            triangle((0.0, 0.0, 0.0), (-1.0, 0.0, 0.0), (0.0, -1.0, 0.0)),
+            triangle((0.0, 0.0, 0.0), (0.0, -1.0, 0.0), (1.0, 0.0, 0.0)),
+        ];
+        let target = Vec3::new(0.0, 0.0, 0.0);
+        for origin in &[
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.5, 0.5, 1.0),
+            Vec3::new(-0.25, 0.5, -1.0),
+        ] {
+            assert_eq!(hits(&fan, *origin, target), 1);
+        }
+    }
+
+    #[test]
+    fn rays_miss_degenerate_and_edge_on_triangles() {
+        let degenerate = [triangle((0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (2.0, 0.0, 0.0))];
+        let target = Vec3::new(0.5, 0.0, 0.0);
+        assert_eq!(hits(&degenerate, Vec3::new(0.5, 0.0, 1.0), target), 0);
+        assert_eq!(hits(&degenerate, Vec3::new(-1.0, 0.0, 0.0), target), 0);
+        assert_eq!(hits(&degenerate, Vec3::new(0.5, 1.0, 0.0), target), 0);
+
+        // a ray in the plane of a triangle grazes it without hitting
+        let flat = [triangle((0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0))];
+        let target = Vec3::new(0.25, 0.25, 0.0);
+        assert_eq!(hits(&flat, Vec3::new(-1.0, 0.25, 0.0), target), 0);
+    }
+
+    // Checks that `pdf_value` is a density over directions from `origin`, by integrating it
+    // against uniform directions, and that `random` draws from it, by comparing the chance of a
+    // sample falling in the half space along `side` with the integral of the density over it.
+    fn assert_pdf_matches_random(object: &dyn Hittable, origin: Vec3, side: Vec3) {
+        let mut rng = Pcg32::new(7, 0);
+        let (samples, sphere) = (200_000, 4.0 * std::f64::consts::PI);
+
+        let (mut total, mut inside) = (0.0, 0.0);
+        for _ in 0..samples {
+            let direction = Vec3::random_unit_vector(&mut rng);
+            let density = object.pdf_value(&origin, &direction) * sphere / samples as f64;
+            total += density;
+            if direction.dot(&side) > 0.0 {
+                inside += density;
+            }
+        }
+        assert!((total - 1.0).abs() < 0.02, "pdf integrates to {}", total);
+
+        let mut sampled = 0;
+        for _ in 0..samples {
+            let direction = object.random(&origin, &mut rng);
+            assert!(object.pdf_value(&origin, &direction) > 0.0);
+            if direction.dot(&side) > 0.0 {
+                sampled += 1;
+            }
+        }
+        let sampled = sampled as f64 / samples as f64;
+        assert!(
+            (0.1..0.9).contains(&inside),
+            "{} is a trivial half space",
+            inside
+        );
+        assert!((sampled - inside).abs() < 0.02, "{} != {}", sampled, inside);
+    }
+
+    #[test]
+    fn sphere_pdf_matches_its_samples() {
+        assert_pdf_matches_random(
+            &unit_sphere(),
+            Vec3::new(1.5, 1.0, -0.5),
+            Vec3::new(1.0, -1.2, 0.0),
+        );
+    }
+
+    #[test]
+    fn quad_pdf_matches_its_samples() {
+        let quad = Quad::new(
+            Vec3::new(-0.5, 1.0, -1.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.5, 0.0, 1.0),
+            std::sync::Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        assert_pdf_matches_random(&quad, Vec3::new(0.2, 0.0, 0.3), Vec3::new(1.0, 0.0, 0.4));
+    }
 }
@@ -1,3 +1,4 @@
+use crate::aabb::Aabb;
 use crate::hittable::{HitRecord, Hittable};
 use crate::ray::Ray;
 
@@ -19,6 +20,10 @@ impl HittableList {
     pub fn clear(&mut self) {
         self.objects.clear();
     }
+
+    pub fn objects(&self) -> &[std::sync::Arc<dyn Hittable>] {
+        &self.objects
+    }
 }
 
 impl Default for HittableList {
@@ -28,18 +33,29 @@ impl Default for HittableList {
 }
 
 impl Hittable for HittableList {
-    fn hit(&self, ray: &Ray, time_min: f64, time_max: f64) -> Option<HitRecord> {
-        let mut closest_so_far = time_max;
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut closest_so_far = t_max;
         let mut closest_hit_record = None;
 
         for object in &self.objects {
-            let hit_record = object.hit(ray, time_min, closest_so_far);
+            let hit_record = object.hit(ray, t_min, closest_so_far);
             if let Some(hit_record) = hit_record {
-                closest_so_far = hit_record.time();
+                closest_so_far = hit_record.t();
                 closest_hit_record = Some(hit_record);
             }
         }
 
         closest_hit_record
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut objects = self.objects.iter();
+        let mut bounding_box = objects.next()?.bounding_box()?;
+
+        for object in objects {
+            bounding_box = bounding_box.surrounding(&object.bounding_box()?);
+        }
+
+        Some(bounding_box)
+    }
 }
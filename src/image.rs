@@ -0,0 +1,696 @@
+use crate::buffer::Buffer;
+use crate::color::Color;
+use crate::pixel::Pixel;
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Clone, Copy)]
+pub enum ImageFormat {
+    Ppm,
+    Png,
+    Png16,
+    Exr,
+    Exr32,
+    Hdr,
+    Pfm,
+}
+
+impl ImageFormat {
+    pub const NAMES: &'static [&'static str] =
+        &["ppm", "png", "png16", "exr", "exr32", "hdr", "pfm"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            "png16" => Some(ImageFormat::Png16),
+            "exr" => Some(ImageFormat::Exr),
+            "exr32" => Some(ImageFormat::Exr32),
+            "hdr" => Some(ImageFormat::Hdr),
+            "pfm" => Some(ImageFormat::Pfm),
+            _ => None,
+        }
+    }
+
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            "exr" => Some(ImageFormat::Exr),
+            "hdr" => Some(ImageFormat::Hdr),
+            "pfm" => Some(ImageFormat::Pfm),
+            _ => None,
+        }
+    }
+}
+
+pub fn write_image<W: Write>(
+    writer: W,
+    buffer: &Buffer,
+    format: ImageFormat,
+) -> std::io::Result<()> {
+    match format {
+        ImageFormat::Ppm => write_ppm(writer, buffer),
+        ImageFormat::Png => write_png(writer, buffer, png::BitDepth::Eight),
+        ImageFormat::Png16 => write_png(writer, buffer, png::BitDepth::Sixteen),
+        ImageFormat::Exr => write_exr(
+            writer,
+            &[ExrLayer::new("beauty", buffer)],
+            ExrPrecision::Half,
+        ),
+        ImageFormat::Exr32 => write_exr(
+            writer,
+            &[ExrLayer::new("beauty", buffer)],
+            ExrPrecision::Float,
+        ),
+        ImageFormat::Hdr => write_hdr(writer, buffer),
+        ImageFormat::Pfm => write_pfm(writer, buffer),
+    }
+}
+
+fn write_ppm<W: Write>(mut writer: W, buffer: &Buffer) -> std::io::Result<()> {
+    writeln!(writer, "P3")?;
+    writeln!(writer, "{} {}", buffer.width(), buffer.height())?;
+    writeln!(writer, "255")?;
+
+    for j in (0..buffer.height()).rev() {
+        for i in 0..buffer.width() {
+            writeln!(writer, "{}", buffer.get(&Pixel::new(i, j)).srgb())?;
+        }
+    }
+
+    writer.flush()
+}
+
+fn write_png<W: Write>(
+    writer: W,
+    buffer: &Buffer,
+    bit_depth: png::BitDepth,
+) -> std::io::Result<()> {
+    let mut encoder = png::Encoder::new(writer, buffer.width() as u32, buffer.height() as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(bit_depth);
+    encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    encoder.set_source_gamma(png::ScaledFloat::from_scaled(45455));
+
+    let max = match bit_depth {
+        png::BitDepth::Sixteen => u16::MAX as f64,
+        _ => u8::MAX as f64,
+    };
+
+    let mut data = Vec::with_capacity(buffer.width() * buffer.height() * 3 * 2);
+    for j in (0..buffer.height()).rev() {
+        for i in 0..buffer.width() {
+            let color = buffer.get(&Pixel::new(i, j)).srgb();
+            for channel in [color.r(), color.g(), color.b()] {
+                let value = (channel.clamp(0.0, 1.0) * max).round();
+                match bit_depth {
+                    png::BitDepth::Sixteen => data.extend_from_slice(&(value as u16).to_be_bytes()),
+                    _ => data.push(value as u8),
+                }
+            }
+        }
+    }
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+pub enum ExrPrecision {
+    Half,
+    Float,
+}
+
+pub struct ExrLayer<'a> {
+    name: &'a str,
+    buffer: &'a Buffer,
+}
+
+impl<'a> ExrLayer<'a> {
+    pub fn new(name: &'a str, buffer: &'a Buffer) -> Self {
+        Self { name, buffer }
+    }
+}
+
+pub fn write_exr<W: Write>(
+    mut writer: W,
+    layers: &[ExrLayer],
+    precision: ExrPrecision,
+) -> std::io::Result<()> {
+    use exr::prelude::*;
+
+    let (width, height) = match layers.first() {
+        Some(layer) => (layer.buffer.width(), layer.buffer.height()),
+        None => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "an exr image needs at least one layer",
+            ))
+        }
+    };
+
+    let layers: Vec<_> = layers
+        .iter()
+        .map(|layer| {
+            let mut channels = [Vec::new(), Vec::new(), Vec::new()];
+            for j in (0..height).rev() {
+                for i in 0..width {
+                    let color = layer.buffer.get(&Pixel::new(i, j));
+                    channels[0].push(color.r() as f32);
+                    channels[1].push(color.g() as f32);
+                    channels[2].push(color.b() as f32);
+                }
+            }
+
+            let [r, g, b] = channels;
+            let samples = |values: Vec<f32>| match precision {
+                ExrPrecision::Half => {
+                    FlatSamples::F16(values.into_iter().map(f16::from_f32).collect())
+                }
+                ExrPrecision::Float => FlatSamples::F32(values),
+            };
+
+            Layer::new(
+                (width, height),
+                LayerAttributes::named(layer.name),
+                Encoding::FAST_LOSSLESS,
+                AnyChannels::sort(SmallVec::from_vec(vec![
+                    AnyChannel::new("R", samples(r)),
+                    AnyChannel::new("G", samples(g)),
+                    AnyChannel::new("B", samples(b)),
+                ])),
+            )
+        })
+        .collect();
+
+    let image = Image::from_layers(
+        ImageAttributes::new(IntegerBounds::from_dimensions((width, height))),
+        layers,
+    );
+
+    let mut encoded = std::io::Cursor::new(Vec::new());
+    image
+        .write()
+        .to_buffered(&mut encoded)
+        .map_err(std::io::Error::other)?;
+
+    writer.write_all(encoded.get_ref())?;
+    writer.flush()
+}
+
+fn write_hdr<W: Write>(mut writer: W, buffer: &Buffer) -> std::io::Result<()> {
+    writeln!(writer, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n")?;
+    writeln!(writer, "-Y {} +X {}", buffer.height(), buffer.width())?;
+
+    let mut scanline = vec![[0u8; 4]; buffer.width()];
+    for j in (0..buffer.height()).rev() {
+        for (i, rgbe) in scanline.iter_mut().enumerate() {
+            let color = buffer.get(&Pixel::new(i, j));
+            *rgbe = rgbe_encode(color.r(), color.g(), color.b());
+        }
+
+        if !(8..0x8000).contains(&buffer.width()) {
+            for rgbe in &scanline {
+                writer.write_all(rgbe)?;
+            }
+            continue;
+        }
+
+        writer.write_all(&[2, 2, (buffer.width() >> 8) as u8, buffer.width() as u8])?;
+        for component in 0..4 {
+            let values: Vec<u8> = scanline.iter().map(|rgbe| rgbe[component]).collect();
+            write_hdr_run_length(&mut writer, &values)?;
+        }
+    }
+
+    writer.flush()
+}
+
+fn rgbe_encode(r: f64, g: f64, b: f64) -> [u8; 4] {
+    let (r, g, b) = (r.max(0.0), g.max(0.0), b.max(0.0));
+    let max = r.max(g).max(b);
+    if max < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    let exponent = max.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2f64.powi(exponent);
+
+    [
+        (r * scale).min(255.0) as u8,
+        (g * scale).min(255.0) as u8,
+        (b * scale).min(255.0) as u8,
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
+
+fn write_hdr_run_length<W: Write>(writer: &mut W, values: &[u8]) -> std::io::Result<()> {
+    let mut position = 0;
+
+    while position < values.len() {
+        let mut run = 1;
+        while position + run < values.len()
+            && run < 127
+            && values[position + run] == values[position]
+        {
+            run += 1;
+        }
+
+        if run >= 3 {
+            writer.write_all(&[128 + run as u8, values[position]])?;
+            position += run;
+            continue;
+        }
+
+        let start = position;
+        while position < values.len() && position - start < 128 {
+            if position + 2 < values.len()
+                && values[position] == values[position + 1]
+                && values[position] == values[position + 2]
+            {
+                break;
+            }
+            position += 1;
+        }
+
+        writer.write_all(&[(position - start) as u8])?;
+        writer.write_all(&values[start..position])?;
+    }
+
+    Ok(())
+}
+
+fn write_pfm<W: Write>(mut writer: W, buffer: &Buffer) -> std::io::Result<()> {
+    write!(writer, "PF\n{} {}\n-1.0\n", buffer.width(), buffer.height())?;
+
+    for j in 0..buffer.height() {
+        for i in 0..buffer.width() {
+            let color = buffer.get(&Pixel::new(i, j));
+            for channel in [color.r(), color.g(), color.b()] {
+                writer.write_all(&(channel as f32).to_le_bytes())?;
+            }
+        }
+    }
+
+    writer.flush()
+}
+
+pub fn read_image(path: &Path) -> std::io::Result<Buffer> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+
+    match ImageFormat::from_extension(path) {
+        Some(ImageFormat::Png) => read_png(file),
+        Some(ImageFormat::Ppm) => read_ppm(file),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("unsupported image format '{}'", path.display()),
+        )),
+    }
+}
+
+fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn read_png<R: std::io::Read>(reader: R) -> std::io::Result<Buffer> {
+    let mut decoder = png::Decoder::new(reader);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader = decoder.read_info()?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data)?;
+
+    let channels = match info.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "unexpanded indexed png",
+            ))
+        }
+    };
+
+    let (width, height) = (info.width as usize, info.height as usize);
+    let mut buffer = Buffer::new(width, height);
+    for (row, line) in data.chunks(info.line_size).take(height).enumerate() {
+        for i in 0..width {
+            let texel = &line[i * channels..];
+            let channel = |c: usize| srgb_to_linear(texel[c] as f64 / 255.0);
+            let color = if channels < 3 {
+                Color::new(channel(0), channel(0), channel(0))
+            } else {
+                Color::new(channel(0), channel(1), channel(2))
+            };
+            buffer.set(&Pixel::new(i, height - 1 - row), color);
+        }
+    }
+
+    Ok(buffer)
+}
+
+fn read_ppm<R: std::io::Read>(mut reader: R) -> std::io::Result<Buffer> {
+    let invalid = |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+
+    let mut position = 0;
+    let mut token = || -> std::io::Result<String> {
+        loop {
+            while position < data.len() && data[position].is_ascii_whitespace() {
+                position += 1;
+            }
+            if position < data.len() && data[position] == b'#' {
+                while position < data.len() && data[position] != b'\n' {
+                    position += 1;
+                }
+                continue;
+            }
+            break;
+        }
+
+        let start = position;
+        while position < data.len() && !data[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        if start == position {
+            return Err(invalid("unexpected end of ppm"));
+        }
+        Ok(String::from_utf8_lossy(&data[start..position]).into_owned())
+    };
+
+    let magic = token()?;
+    let number = |token: String| -> std::io::Result<usize> {
+        token.parse().map_err(|_| invalid("invalid number in ppm"))
+    };
+    let width = number(token()?)?;
+    let height = number(token()?)?;
+    let max = number(token()?)?;
+    if width == 0 || height == 0 {
+        return Err(invalid("empty ppm"));
+    }
+    if max == 0 || max > 65535 {
+        return Err(invalid("ppm maximum value must be between 1 and 65535"));
+    }
+    let count = width
+        .checked_mul(height)
+        .and_then(|count| count.checked_mul(3))
+        .ok_or_else(|| invalid("ppm is too large"))?;
+    let max = max as f64;
+
+    // the header is not trusted to size the allocation, since every sample takes at least a byte
+    let mut samples = Vec::with_capacity(count.min(data.len()));
+    match magic.as_str() {
+        "P3" => {
+            for _ in 0..count {
+                samples.push(number(token()?)? as f64 / max);
+            }
+        }
+        "P6" => {
+            let bytes_per_sample = if max < 256.0 { 1 } else { 2 };
+            let raster = data.get(position + 1..).unwrap_or(&[]);
+            if count
+                .checked_mul(bytes_per_sample)
+                .is_none_or(|size| raster.len() < size)
+            {
+                return Err(invalid("truncated ppm raster"));
+            }
+            for sample in raster.chunks(bytes_per_sample).take(count) {
+                let value = sample
+                    .iter()
+                    .fold(0, |value, &byte| value * 256 + byte as usize);
+                samples.push(value as f64 / max);
+            }
+        }
+        _ => return Err(invalid("unsupported ppm variant")),
+    }
+
+    let mut buffer = Buffer::new(width, height);
+    for (index, texel) in samples.chunks(3).enumerate() {
+        let (i, row) = (index % width, index / width);
+        buffer.set(
+            &Pixel::new(i, height - 1 - row),
+            Color::new(
+                srgb_to_linear(texel[0]),
+                srgb_to_linear(texel[1]),
+                srgb_to_linear(texel[2]),
+            ),
+        );
+    }
+
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(data: &[u8]) -> std::io::Result<Buffer> {
+        read_ppm(data)
+    }
+
+    fn assert_invalid(data: &[u8]) {
+        match read(data) {
+            Err(error) => assert_eq!(error.kind(), std::io::ErrorKind::InvalidData),
+            Ok(_) => panic!("{:?} was accepted", String::from_utf8_lossy(data)),
+        }
+    }
+
+    #[test]
+    fn reads_plain_ppm() {
+        let buffer = read(b"P3\n# comment\n2 1\n255\n255 0 0  0 0 255\n").unwrap();
+        assert_eq!((buffer.width(), buffer.height()), (2, 1));
+        assert_eq!(buffer.get(&Pixel::new(0, 0)).r(), 1.0);
+        assert_eq!(buffer.get(&Pixel::new(1, 0)).b(), 1.0);
+    }
+
+    #[test]
+    fn reads_binary_ppm() {
+        let buffer = read(b"P6 1 2 255\n\xff\x00\x00\x00\xff\x00").unwrap();
+        // rows are stored top to bottom, the buffer bottom to top
+        assert_eq!(buffer.get(&Pixel::new(0, 1)).r(), 1.0);
+        assert_eq!(buffer.get(&Pixel::new(0, 0)).g(), 1.0);
+
+        let buffer = read(b"P6 1 1 65535\n\xff\xff\x00\x00\x00\x00").unwrap();
+        assert_eq!(buffer.get(&Pixel::new(0, 0)).r(), 1.0);
+    }
+
+    #[test]
+    fn rejects_sizes_that_do_not_fit() {
+        assert_invalid(b"P6 18446744073709551615 18446744073709551615 255\n");
+        assert_invalid(b"P6 100000 100000 255\n\x00\x00\x00");
+        assert_invalid(b"P3 100000 100000 255\n0 0 0");
+    }
+
+    #[test]
+    fn rejects_headers_ending_at_the_end_of_the_file() {
+        assert_invalid(b"P6 1 1 255");
+        assert_invalid(b"P6 1 1 255\n");
+        assert_invalid(b"P6 1 1");
+    }
+
+    #[test]
+    fn rejects_invalid_maximum_values() {
+        assert_invalid(b"P3 1 1 0\n0 0 0");
+        assert_invalid(b"P3 1 1 65536\n0 0 0");
+    }
+
+    #[test]
+    fn rejects_empty_images() {
+        assert_invalid(b"P3 0 1 255\n");
+        assert_invalid(b"P6 1 0 255\n");
+    }
+
+    // A buffer whose rows mix distinct pixels with a run of exactly `run` equal ones.
+    fn image(width: usize, height: usize, run: usize) -> Buffer {
+        let mut buffer = Buffer::new(width, height);
+        for j in 0..height {
+            for i in 0..width {
+                let color = if (10..10 + run).contains(&i) {
+                    Color::new(0.5, 2.0, 0.25)
+                } else {
+                    let x = (i * 7 + j * 13) as f64;
+                    Color::new(x / 97.0 % 3.0, x / 31.0 % 1.0, x / 11.0 % 0.5)
+                };
+                buffer.set(&Pixel::new(i, j), color);
+            }
+        }
+        buffer
+    }
+
+    fn write(buffer: &Buffer, format: ImageFormat) -> Vec<u8> {
+        let mut data = Vec::new();
+        write_image(&mut data, buffer, format).unwrap();
+        data
+    }
+
+    // Splits a header of `lines` newline-terminated lines from the data that follows it.
+    fn split_header(data: &[u8], lines: usize) -> (String, &[u8]) {
+        let mut end = 0;
+        for _ in 0..lines {
+            end += data[end..].iter().position(|&byte| byte == b'\n').unwrap() + 1;
+        }
+        (
+            String::from_utf8_lossy(&data[..end]).into_owned(),
+            &data[end..],
+        )
+    }
+
+    // Decodes the scanlines of a radiance hdr, asserting they are run-length encoded exactly
+    // when the width allows it.
+    fn read_hdr(data: &[u8], width: usize, height: usize) -> Vec<[u8; 4]> {
+        let (header, mut data) = split_header(data, 4);
+        assert!(header.ends_with(&format!("\n-Y {} +X {}\n", height, width)));
+
+        let mut pixels = Vec::new();
+        for _ in 0..height {
+            if !(8..0x8000).contains(&width) {
+                pixels.extend(
+                    data[..width * 4]
+                        .chunks(4)
+                        .map(|rgbe| [rgbe[0], rgbe[1], rgbe[2], rgbe[3]]),
+                );
+                data = &data[width * 4..];
+                continue;
+            }
+
+            assert_eq!(data[..4], [2, 2, (width >> 8) as u8, width as u8]);
+            data = &data[4..];
+            let mut scanline = vec![[0; 4]; width];
+            for component in 0..4 {
+                let mut values = Vec::new();
+                while values.len() < width {
+                    let count = data[0] as usize;
+                    assert_ne!(count, 0);
+                    if count > 128 {
+                        values.extend(std::iter::repeat_n(data[1], count - 128));
+                        data = &data[2..];
+                    } else {
+                        values.extend_from_slice(&data[1..1 + count]);
+                        data = &data[1 + count..];
+                    }
+                }
+                assert_eq!(values.len(), width);
+                for (rgbe, value) in scanline.iter_mut().zip(values) {
+                    rgbe[component] = value;
+                }
+            }
+            pixels.extend(scanline);
+        }
+        assert!(data.is_empty());
+        pixels
+    }
+
+    #[test]
+    fn hdr_round_trips() {
+        // flat scanlines below 8 and above 32767 pixels, run-length encoded ones between
+        for &(width, height, run) in &[(5, 3, 0), (300, 2, 128), (200, 2, 127), (0x8000, 1, 200)] {
+            let buffer = image(width, height, run);
+            let pixels = read_hdr(&write(&buffer, ImageFormat::Hdr), width, height);
+
+            let mut expected = Vec::new();
+            for j in (0..height).rev() {
+                for i in 0..width {
+                    let color = buffer.get(&Pixel::new(i, j));
+                    expected.push(rgbe_encode(color.r(), color.g(), color.b()));
+                }
+            }
+            assert!(
+                pixels == expected,
+                "{}x{} hdr did not round trip",
+                width,
+                height
+            );
+        }
+    }
+
+    #[test]
+    fn pfm_round_trips() {
+        let buffer = image(20, 3, 5);
+        let data = write(&buffer, ImageFormat::Pfm);
+        let (header, data) = split_header(&data, 3);
+        assert_eq!(header, "PF\n20 3\n-1.0\n");
+        assert_eq!(data.len(), 20 * 3 * 3 * 4);
+
+        // rows are stored bottom to top, like the buffer
+        let mut values = data
+            .chunks(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        for j in 0..3 {
+            for i in 0..20 {
+                let color = buffer.get(&Pixel::new(i, j));
+                for channel in [color.r(), color.g(), color.b()] {
+                    assert_eq!(values.next(), Some(channel as f32));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn exr_round_trips() {
+        use exr::prelude::{read, ReadChannels, ReadLayers};
+
+        let (beauty, heatmap) = (image(12, 5, 4), image(12, 5, 0));
+        for &(precision, tolerance) in &[(ExrPrecision::Float, 1e-6), (ExrPrecision::Half, 1e-3)] {
+            let mut data = Vec::new();
+            let layers = [
+                ExrLayer::new("beauty", &beauty),
+                ExrLayer::new("heatmap", &heatmap),
+            ];
+            write_exr(&mut data, &layers, precision).unwrap();
+
+            let image = read()
+                .no_deep_data()
+                .largest_resolution_level()
+                .all_channels()
+                .all_layers()
+                .all_attributes()
+                .from_buffered(std::io::Cursor::new(data))
+                .unwrap();
+            let names: Vec<_> = image
+                .layer_data
+                .iter()
+                .map(|layer| layer.attributes.layer_name.as_ref().unwrap().to_string())
+                .collect();
+            assert_eq!(names, ["beauty", "heatmap"]);
+
+            for (layer, buffer) in image.layer_data.iter().zip([&beauty, &heatmap]) {
+                let channel = |name: &str| -> Vec<f32> {
+                    let channels = &layer.channel_data.list;
+                    let channel = channels.iter().find(|channel| channel.name == *name);
+                    channel.unwrap().sample_data.values_as_f32().collect()
+                };
+                let (r, g, b) = (channel("R"), channel("G"), channel("B"));
+                assert_eq!((r.len(), g.len(), b.len()), (60, 60, 60));
+
+                // rows are stored top to bottom
+                for (index, ((r, g), b)) in r.iter().zip(&g).zip(&b).enumerate() {
+                    let pixel = Pixel::new(index % 12, 4 - index / 12);
+                    let color = buffer.get(&pixel);
+                    for (value, channel) in [(r, color.r()), (g, color.g()), (b, color.b())] {
+                        let error = (*value as f64 - channel).abs();
+                        assert!(error <= tolerance * channel.max(1.0));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn exr_needs_a_layer() {
+        let error = write_exr(Vec::new(), &[], ExrPrecision::Half).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
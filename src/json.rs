@@ -0,0 +1,357 @@
+use std::fmt::{Display, Formatter};
+
+pub struct JsonValue {
+    line: usize,
+    kind: JsonKind,
+}
+
+pub enum JsonKind {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn kind(&self) -> &JsonKind {
+        &self.kind
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match &self.kind {
+            JsonKind::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self.kind {
+            JsonKind::Null => "null",
+            JsonKind::Bool(_) => "boolean",
+            JsonKind::Number(_) => "number",
+            JsonKind::String(_) => "string",
+            JsonKind::Array(_) => "array",
+            JsonKind::Object(_) => "object",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct JsonError {
+    line: usize,
+    message: String,
+}
+
+impl JsonError {
+    pub fn new(line: usize, message: String) -> Self {
+        Self { line, message }
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+pub fn parse(source: &str) -> Result<JsonValue, JsonError> {
+    let mut parser = Parser {
+        chars: source.chars().collect(),
+        position: 0,
+        line: 1,
+    };
+
+    let value = parser.value()?;
+    parser.whitespace();
+    if parser.peek().is_some() {
+        return Err(parser.error("trailing characters after value"));
+    }
+
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> JsonError {
+        JsonError::new(self.line, message.to_string())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(&format!("expected '{}', found '{}'", expected, c))),
+            None => Err(self.error(&format!("expected '{}', found end of input", expected))),
+        }
+    }
+
+    fn whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<JsonValue, JsonError> {
+        self.whitespace();
+        let line = self.line;
+
+        let kind = match self.peek() {
+            Some('{') => self.object()?,
+            Some('[') => self.array()?,
+            Some('"') => JsonKind::String(self.string()?),
+            Some('t') => self.literal("true", JsonKind::Bool(true))?,
+            Some('f') => self.literal("false", JsonKind::Bool(false))?,
+            Some('n') => self.literal("null", JsonKind::Null)?,
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number()?,
+            Some(c) => return Err(self.error(&format!("unexpected character '{}'", c))),
+            None => return Err(self.error("unexpected end of input")),
+        };
+
+        Ok(JsonValue { line, kind })
+    }
+
+    fn literal(&mut self, literal: &str, kind: JsonKind) -> Result<JsonKind, JsonError> {
+        for expected in literal.chars() {
+            if self.peek() != Some(expected) {
+                return Err(self.error(&format!("invalid literal, expected '{}'", literal)));
+            }
+            self.next();
+        }
+        Ok(kind)
+    }
+
+    fn number(&mut self) -> Result<JsonKind, JsonError> {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                self.next();
+            } else {
+                break;
+            }
+        }
+
+        let text: String = self.chars[start..self.position].iter().collect();
+        text.parse()
+            .map(JsonKind::Number)
+            .map_err(|_| self.error(&format!("invalid number '{}'", text)))
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+        let mut string = String::new();
+
+        loop {
+            // a newline would move on to the next line before the error is reported
+            if self.peek() == Some('\n') {
+                return Err(self.error("unterminated string"));
+            }
+
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => match self.next() {
+                    Some('"') => string.push('"'),
+                    Some('\\') => string.push('\\'),
+                    Some('/') => string.push('/'),
+                    Some('b') => string.push('\u{8}'),
+                    Some('f') => string.push('\u{c}'),
+                    Some('n') => string.push('\n'),
+                    Some('r') => string.push('\r'),
+                    Some('t') => string.push('\t'),
+                    Some('u') => {
+                        let mut code = self.code_unit()?;
+                        // characters outside the basic multilingual plane are escaped as a
+                        // UTF-16 surrogate pair
+                        if (0xd800..0xdc00).contains(&code) {
+                            if self.next() != Some('\\') || self.next() != Some('u') {
+                                return Err(self.error("unpaired surrogate in unicode escape"));
+                            }
+                            let low = self.code_unit()?;
+                            if !(0xdc00..0xe000).contains(&low) {
+                                return Err(self.error("unpaired surrogate in unicode escape"));
+                            }
+                            code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                        }
+                        string.push(
+                            char::from_u32(code)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?,
+                        );
+                    }
+                    _ => return Err(self.error("invalid escape sequence")),
+                },
+                Some(c) => string.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    // The four hexadecimal digits of a unicode escape.
+    fn code_unit(&mut self) -> Result<u32, JsonError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("invalid unicode escape"))?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    fn array(&mut self) -> Result<JsonKind, JsonError> {
+        self.expect('[')?;
+        let mut elements = Vec::new();
+
+        self.whitespace();
+        if self.peek() == Some(']') {
+            self.next();
+            return Ok(JsonKind::Array(elements));
+        }
+
+        loop {
+            elements.push(self.value()?);
+            self.whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(JsonKind::Array(elements)),
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<JsonKind, JsonError> {
+        self.expect('{')?;
+        let mut members: Vec<(String, JsonValue)> = Vec::new();
+
+        self.whitespace();
+        if self.peek() == Some('}') {
+            self.next();
+            return Ok(JsonKind::Object(members));
+        }
+
+        loop {
+            self.whitespace();
+            let line = self.line;
+            let key = self.string()?;
+            if members.iter().any(|(name, _)| name == &key) {
+                return Err(JsonError::new(line, format!("duplicate key '{}'", key)));
+            }
+
+            self.whitespace();
+            self.expect(':')?;
+            members.push((key, self.value()?));
+
+            self.whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(JsonKind::Object(members)),
+                _ => return Err(self.error("expected ',' or '}' in object")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> (usize, String) {
+        match parse(source) {
+            Err(error) => (error.line(), error.message().to_string()),
+            Ok(_) => panic!("the source parsed"),
+        }
+    }
+
+    fn string(source: &str) -> String {
+        match parse(source).map(|value| value.kind) {
+            Ok(JsonKind::String(string)) => string,
+            Ok(_) => panic!("not a string"),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    #[test]
+    fn syntax_errors_report_their_line() {
+        assert_eq!(
+            error("{\n  \"a\": 1,\n  \"b\": [1 2]\n}"),
+            (3, String::from("expected ',' or ']' in array"))
+        );
+        assert_eq!(
+            error("{\n  \"a\": tru\n}"),
+            (2, String::from("invalid literal, expected 'true'"))
+        );
+    }
+
+    #[test]
+    fn unterminated_strings_report_their_line() {
+        assert_eq!(
+            error("{\n  \"a\": \"b\n}"),
+            (2, String::from("unterminated string"))
+        );
+    }
+
+    #[test]
+    fn duplicate_keys_report_the_line_of_the_second_key() {
+        assert_eq!(
+            error("{\n  \"a\": 1,\n\n  \"a\": 2\n}"),
+            (4, String::from("duplicate key 'a'"))
+        );
+    }
+
+    #[test]
+    fn unicode_escapes_decode_surrogate_pairs() {
+        assert_eq!(string(r#""caf\u00e9""#), "caf\u{e9}");
+        assert_eq!(string(r#""\uD83D\uDE00""#), "\u{1f600}");
+        assert_eq!(
+            error(r#""\uD83D""#),
+            (1, String::from("unpaired surrogate in unicode escape"))
+        );
+        assert_eq!(
+            error(r#""\uD83DA""#),
+            (1, String::from("unpaired surrogate in unicode escape"))
+        );
+        assert_eq!(
+            error(r#""\uDE00""#),
+            (1, String::from("invalid unicode escape"))
+        );
+    }
+}
@@ -0,0 +1,68 @@
+pub(crate) mod aabb;
+pub(crate) mod background;
+pub(crate) mod buffer;
+pub(crate) mod bvh;
+pub(crate) mod camera;
+pub(crate) mod checkpoint;
+pub(crate) mod color;
+pub(crate) mod compute;
+pub(crate) mod film;
+pub(crate) mod grid;
+pub(crate) mod hittable;
+pub(crate) mod hittable_list;
+pub(crate) mod image;
+pub(crate) mod json;
+pub(crate) mod material;
+pub(crate) mod matrix;
+pub(crate) mod medium;
+pub(crate) mod mesh;
+pub(crate) mod number;
+pub(crate) mod obj;
+pub(crate) mod onb;
+pub(crate) mod pdf;
+pub(crate) mod perlin;
+pub(crate) mod pixel;
+pub(crate) mod polynomial;
+pub(crate) mod quadric;
+pub(crate) mod ray;
+pub(crate) mod render;
+pub(crate) mod scene;
+pub(crate) mod texture;
+pub(crate) mod tile;
+pub(crate) mod vec3;
+
+pub use crate::aabb::Aabb;
+pub use crate::background::Background;
+pub use crate::buffer::Buffer;
+pub use crate::bvh::BvhNode;
+pub use crate::camera::Camera;
+pub use crate::checkpoint::{read_checkpoint, write_checkpoint};
+pub use crate::color::Color;
+pub use crate::film::Film;
+pub use crate::grid::VoxelGrid;
+pub use crate::hittable::{
+    Axis, Cuboid, HitRecord, Hittable, Moving, MovingSphere, Plane, Quad, Rectangle, Sphere,
+    Transformed, Triangle,
+};
+pub use crate::hittable_list::HittableList;
+pub use crate::image::{read_image, write_exr, write_image, ExrLayer, ExrPrecision, ImageFormat};
+pub use crate::material::{
+    Dielectric, DiffuseLight, HenyeyGreenstein, Isotropic, Lambertian, Material, Metal,
+};
+pub use crate::matrix::{Matrix4, Transform};
+pub use crate::medium::{ConstantMedium, GridMedium};
+pub use crate::mesh::{Mesh, MeshData, MeshFace};
+pub use crate::number::Pcg32;
+pub use crate::obj::{load as load_obj, ObjError};
+pub use crate::perlin::Perlin;
+pub use crate::pixel::Pixel;
+pub use crate::quadric::{Annulus, Cone, Cylinder, Disk, Torus};
+pub use crate::ray::Ray;
+pub use crate::render::{RenderError, Renderer};
+pub use crate::scene::{RenderSettings, Scene, SceneError};
+pub use crate::texture::{
+    CheckerTexture, ImageTexture, MarbleTexture, NoiseTexture, SolidColor, Texture, WoodTexture,
+    WrapMode,
+};
+pub use crate::tile::Tile;
+pub use crate::vec3::Vec3;
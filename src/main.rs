@@ -1,187 +1,128 @@
-mod buffer;
-mod camera;
-mod color;
-mod compute;
-mod hittable;
-mod hittable_list;
-mod material;
-mod number;
-mod pixel;
-mod ray;
-mod vec3;
+mod cli;
 
-use crate::buffer::Buffer;
-use crate::camera::Camera;
-use crate::color::Color;
-use crate::compute::Compute;
-use crate::hittable::{Hittable, Sphere};
-use crate::hittable_list::HittableList;
-use crate::material::{Dielectric, Lambertian, Metal};
-use crate::number::random_f64;
-use crate::pixel::Pixel;
-use crate::ray::Ray;
-use crate::vec3::Vec3;
+use crate::cli::Options;
+use ray_tracing_in_one_weekend::{
+    read_checkpoint, write_checkpoint, write_image, Film, Pcg32, Renderer, Scene,
+};
+use std::path::{Path, PathBuf};
 
-fn random_scene() -> HittableList {
-    let mut world = HittableList::new();
-
-    let ground_material = std::sync::Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
-    world.add(std::sync::Arc::new(Sphere::new(
-        Vec3::new(0.0, -1000.0, 0.0),
-        1000.0,
-        ground_material,
-    )));
-
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_material = random_f64(None);
-            let center = Vec3::new(
-                a as f64 + 0.9 * random_f64(None),
-                0.2,
-                b as f64 + 0.9 * random_f64(None),
-            );
-
-            if (center - Vec3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                if choose_material < 0.8 {
-                    let albedo = Color::random(None) * Color::random(None);
-                    let material = std::sync::Arc::new(Lambertian::new(albedo));
-                    world.add(std::sync::Arc::new(Sphere::new(center, 0.2, material)));
-                } else if choose_material < 0.95 {
-                    let albedo = Color::random(Some((0.5, 1.0)));
-                    let fuzz = random_f64(Some((0.0, 0.5)));
-                    let material = std::sync::Arc::new(Metal::new(albedo, fuzz));
-                    world.add(std::sync::Arc::new(Sphere::new(center, 0.2, material)));
-                } else {
-                    let material = std::sync::Arc::new(Dielectric::new(1.5));
-                    world.add(std::sync::Arc::new(Sphere::new(center, 0.2, material)));
-                }
-            }
-        }
-    }
-
-    let material_1 = std::sync::Arc::new(Dielectric::new(1.5));
-    world.add(std::sync::Arc::new(Sphere::new(
-        Vec3::new(0.0, 1.0, 0.0),
-        1.0,
-        material_1,
-    )));
-
-    let material_2 = std::sync::Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
-    world.add(std::sync::Arc::new(Sphere::new(
-        Vec3::new(-4.0, 1.0, 0.0),
-        1.0,
-        material_2,
-    )));
-
-    let material_3 = std::sync::Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
-    world.add(std::sync::Arc::new(Sphere::new(
-        Vec3::new(4.0, 1.0, 0.0),
-        1.0,
-        material_3,
-    )));
-
-    world
-}
+const CHECKPOINT_PASS_SAMPLES: usize = 16;
 
 fn main() {
-    // image
-    let aspect_ratio = 16.0 / 9.0;
-    let image_width: usize = 1920;
-    let image_height: usize = (image_width as f64 / aspect_ratio) as usize;
-    let samples_per_pixel: usize = 500;
-    let max_depth: usize = 50;
-
-    // camera
-    let camera = Camera::new(
-        Vec3::new(13.0, 2.0, 3.0),
-        Vec3::new(0.0, 0.0, 0.0),
-        Vec3::new(0.0, 1.0, 0.0),
-        20.0,
-        aspect_ratio,
-        0.1,
-        10.0,
-    );
-    let camera = std::sync::Arc::new(camera);
-
-    // world
-    let world = random_scene();
-
-    let world: std::sync::Arc<(dyn Hittable + 'static + Send)> = std::sync::Arc::new(world);
-
-    // processor
-    let mut functions = Vec::new();
-    for _ in 0..num_cpus::get() {
-        let camera = camera.clone();
-        let world = world.clone();
-        functions.push(move |pixel: &Pixel| {
-            fn ray_color(
-                ray: &Ray,
-                world: &std::sync::Arc<(dyn Hittable + 'static + Send)>,
-                depth: usize,
-            ) -> Color {
-                if depth == 0 {
-                    return Color::new(0.0, 0.0, 0.0);
-                }
-
-                if let Some(hit_record) = world.hit(ray, 0.001, f64::INFINITY) {
-                    return if let Some((attenuation, scattered)) =
-                        hit_record.material().scatter(ray, &hit_record)
-                    {
-                        attenuation * ray_color(&scattered, world, depth - 1)
-                    } else {
-                        Color::new(0.0, 0.0, 0.0)
-                    };
-                }
-                let unit_direction = ray.direction().unit_vector();
-                let t = 0.5 * (unit_direction.y() + 1.0);
-                (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0)
+    let options = Options::parse();
+
+    // checkpoint
+    let checkpoint = options.resume().map(|path| {
+        std::fs::File::open(path)
+            .and_then(|file| read_checkpoint(std::io::BufReader::new(file)))
+            .unwrap_or_else(|error| {
+                eprintln!("failed to read checkpoint {}: {}", path.display(), error);
+                std::process::exit(1);
+            })
+    });
+    let seed = checkpoint
+        .as_ref()
+        .map_or_else(|| options.seed(), |(seed, _, _)| *seed);
+
+    // scene
+    let scene = match options.scene() {
+        Some(path) => match Scene::load(path) {
+            Ok(scene) => scene,
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
             }
-
-            let mut color = Color::new(0.0, 0.0, 0.0);
-            for _ in 0..samples_per_pixel {
-                let u = (pixel.i() as f64 + random_f64(None)) / (image_width as f64 - 1.0);
-                let v = (pixel.j() as f64 + random_f64(None)) / (image_height as f64 - 1.0);
-                let ray = camera.get_ray(u, v);
-                color = color + ray_color(&ray, &world, max_depth);
+        },
+        None => Scene::random(&mut Pcg32::new(seed, 0)),
+    };
+    let settings = options.render_settings(scene.settings());
+    let samples_per_pixel = settings.samples_per_pixel();
+
+    let scene = scene.with_settings(settings);
+
+    let fingerprint = scene.fingerprint();
+    let film = match checkpoint {
+        Some((_, checkpoint_fingerprint, film)) => {
+            if checkpoint_fingerprint != fingerprint {
+                eprintln!(
+                    "checkpoint was rendered from a different scene, image size or maximum depth"
+                );
+                std::process::exit(1);
             }
-            color
+            film
+        }
+        None => Film::new(scene.settings().width(), scene.settings().height()),
+    };
+
+    // render, saving the image and checkpoint after every pass
+    let pass_samples = match (options.pass_samples(), options.checkpoint()) {
+        (Some(pass_samples), _) => pass_samples,
+        (None, Some(_)) => CHECKPOINT_PASS_SAMPLES,
+        (None, None) => samples_per_pixel,
+    };
+    let save_pass = |film: &Film| {
+        if let Some(path) = options.output() {
+            save(path, "image", |writer| {
+                write_image(writer, &film.to_buffer(), options.format())
+            });
+        }
+        if let Some(path) = options.checkpoint() {
+            save(path, "checkpoint", |writer| {
+                write_checkpoint(writer, seed, fingerprint, film)
+            });
+        }
+    };
+
+    let mut passes = 0;
+    let film = Renderer::new(options.threads())
+        .with_tile_size(options.tile_size())
+        .with_seed(seed)
+        .resume(scene, film, pass_samples, |film| {
+            passes += 1;
+            save_pass(film);
+        })
+        .unwrap_or_else(|error| {
+            eprintln!("failed to resume checkpoint: {}", error);
+            std::process::exit(1);
         });
-    }
 
-    let (compute, receiver) = Compute::new(functions);
+    // a resumed render may already have all of its samples
+    if passes == 0 {
+        save_pass(&film);
+    }
 
-    // orchestrator
-    let mut jobs = Vec::with_capacity(image_height * image_width);
-    for j in 0..image_height {
-        for i in 0..image_width {
-            jobs.push(Pixel::new(i, j));
+    if options.output().is_none() {
+        let stdout = std::io::BufWriter::new(std::io::stdout().lock());
+        if let Err(error) = write_image(stdout, &film.to_buffer(), options.format()) {
+            eprintln!("failed to write image: {}", error);
+            std::process::exit(1);
         }
     }
 
-    let buffer = compute.compute_all(
-        &receiver,
-        jobs,
-        |mut buffer, pixel, color| {
-            buffer.set(pixel, color);
-            buffer
-        },
-        Buffer::new(image_width, image_height),
-    );
-
-    // save buffer
-    println!("P3");
-    println!("{} {}", image_width, image_height);
-    println!("255");
+    // save heatmap
+    if let Some((path, format)) = options.heatmap() {
+        save(path, "heatmap", |writer| {
+            write_image(writer, &film.heatmap(samples_per_pixel), *format)
+        });
+    }
+}
 
-    for j in (0..image_height).rev() {
-        for i in 0..image_width {
-            println!(
-                "{}",
-                buffer
-                    .get(&Pixel::new(i, j))
-                    .sampled(samples_per_pixel)
-                    .gamma()
-            )
-        }
+// Writes next to `path` and renames over it, so an interrupted render always leaves the last
+// complete file behind.
+fn save<F>(path: &Path, name: &str, write: F)
+where
+    F: FnOnce(std::io::BufWriter<std::fs::File>) -> std::io::Result<()>,
+{
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+
+    let result = std::fs::File::create(&partial)
+        .and_then(|file| write(std::io::BufWriter::new(file)))
+        .and_then(|_| std::fs::rename(&partial, path));
+
+    if let Err(error) = result {
+        eprintln!("failed to write {}: {}", name, error);
+        std::process::exit(1);
     }
 }
@@ -1,58 +1,90 @@
 use crate::color::Color;
 use crate::hittable::HitRecord;
-use crate::number::random_f64;
+use crate::number::Pcg32;
+use crate::onb::Onb;
+use crate::pdf::{CosinePdf, Pdf};
 use crate::ray::Ray;
+use crate::texture::{SolidColor, Texture};
 use crate::vec3::Vec3;
 
 pub trait Material: Send + Sync {
-    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray)>;
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord, rng: &mut Pcg32) -> Option<(Color, Ray)>;
+
+    // Density with which `scatter` generates `scattered`, or None for specular materials whose
+    // scattered rays cannot be evaluated for arbitrary directions (and so are not light sampled).
+    fn scattering_pdf(&self, _ray: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> Option<f64> {
+        None
+    }
+
+    fn emitted(&self, _hit_record: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
 }
 
 pub struct Lambertian {
-    albedo: Color,
+    albedo: std::sync::Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Self {
+        Self::textured(std::sync::Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn textured(albedo: std::sync::Arc<dyn Texture>) -> Self {
         Self { albedo }
     }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray)> {
-        let mut scatter_direction = hit_record.normal() + Vec3::random_unit_vector();
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord, rng: &mut Pcg32) -> Option<(Color, Ray)> {
+        let mut scatter_direction = hit_record.normal() + Vec3::random_unit_vector(rng);
 
         if scatter_direction.near_zero() {
-            scatter_direction = hit_record.normal().clone();
+            scatter_direction = *hit_record.normal();
         }
 
-        let scattered = Ray::new(hit_record.point().clone(), scatter_direction);
-        Some((self.albedo.clone(), scattered))
+        let scattered = Ray::new(*hit_record.point(), scatter_direction, ray.time());
+        let attenuation = self
+            .albedo
+            .value(hit_record.u(), hit_record.v(), hit_record.point());
+        Some((attenuation, scattered))
+    }
+
+    fn scattering_pdf(&self, _ray: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Option<f64> {
+        Some(CosinePdf::new(hit_record.normal()).value(scattered.direction()))
     }
 }
 
 pub struct Metal {
-    albedo: Color,
+    albedo: std::sync::Arc<dyn Texture>,
     fuzz: f64,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: f64) -> Self {
+        Self::textured(std::sync::Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    pub fn textured(albedo: std::sync::Arc<dyn Texture>, fuzz: f64) -> Self {
         let fuzz = if fuzz < 1.0 { fuzz } else { 1.0 };
         Self { albedo, fuzz }
     }
 }
 
 impl Material for Metal {
-    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray)> {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord, rng: &mut Pcg32) -> Option<(Color, Ray)> {
         let reflected = ray.direction().unit_vector().reflect(hit_record.normal());
         let scattered = Ray::new(
-            hit_record.point().clone(),
-            reflected + self.fuzz * Vec3::random_in_unit_sphere(),
+            *hit_record.point(),
+            reflected + self.fuzz * Vec3::random_in_unit_sphere(rng),
+            ray.time(),
         );
 
         if scattered.direction().dot(hit_record.normal()) > 0.0 {
-            Some((self.albedo.clone(), scattered))
+            let attenuation = self
+                .albedo
+                .value(hit_record.u(), hit_record.v(), hit_record.point());
+            Some((attenuation, scattered))
         } else {
             None
         }
@@ -78,7 +110,7 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray)> {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord, rng: &mut Pcg32) -> Option<(Color, Ray)> {
         let attenuation = Color::new(1.0, 1.0, 1.0);
         let refraction_ratio = if hit_record.front_face() {
             1.0 / self.index_of_refraction
@@ -93,14 +125,135 @@ impl Material for Dielectric {
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
 
         let direction = if cannot_refract
-            || Self::reflectance(cos_theta, refraction_ratio) > random_f64(None)
+            || Self::reflectance(cos_theta, refraction_ratio) > rng.random_f64(None)
         {
             unit_direction.reflect(hit_record.normal())
         } else {
             unit_direction.refract(hit_record.normal(), refraction_ratio)
         };
 
-        let scattered = Ray::new(hit_record.point().clone(), direction);
+        let scattered = Ray::new(*hit_record.point(), direction, ray.time());
+        Some((attenuation, scattered))
+    }
+}
+
+pub struct DiffuseLight {
+    emit: std::sync::Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self::textured(std::sync::Arc::new(SolidColor::new(emit)))
+    }
+
+    pub fn textured(emit: std::sync::Arc<dyn Texture>) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        _hit_record: &HitRecord,
+        _rng: &mut Pcg32,
+    ) -> Option<(Color, Ray)> {
+        None
+    }
+
+    fn emitted(&self, hit_record: &HitRecord) -> Color {
+        self.emit
+            .value(hit_record.u(), hit_record.v(), hit_record.point())
+    }
+}
+
+// Scatters uniformly in all directions, as a phase function for volumes.
+pub struct Isotropic {
+    albedo: std::sync::Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Self::textured(std::sync::Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn textured(albedo: std::sync::Arc<dyn Texture>) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord, rng: &mut Pcg32) -> Option<(Color, Ray)> {
+        let scattered = Ray::new(
+            *hit_record.point(),
+            Vec3::random_unit_vector(rng),
+            ray.time(),
+        );
+        let attenuation = self
+            .albedo
+            .value(hit_record.u(), hit_record.v(), hit_record.point());
+        Some((attenuation, scattered))
+    }
+
+    fn scattering_pdf(&self, _ray: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> Option<f64> {
+        Some(1.0 / (4.0 * std::f64::consts::PI))
+    }
+}
+
+// A phase function for volumes that favours scattering forwards for a positive `anisotropy` and
+// backwards for a negative one, and is isotropic at zero.
+pub struct HenyeyGreenstein {
+    albedo: std::sync::Arc<dyn Texture>,
+    anisotropy: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, anisotropy: f64) -> Self {
+        Self::textured(std::sync::Arc::new(SolidColor::new(albedo)), anisotropy)
+    }
+
+    pub fn textured(albedo: std::sync::Arc<dyn Texture>, anisotropy: f64) -> Self {
+        Self {
+            albedo,
+            anisotropy: anisotropy.clamp(-0.99, 0.99),
+        }
+    }
+
+    // Density over the sphere of scattering by an angle with the given cosine.
+    fn value(&self, cosine: f64) -> f64 {
+        let g = self.anisotropy;
+        let denominator = 1.0 + g * g - 2.0 * g * cosine;
+        (1.0 - g * g) / (4.0 * std::f64::consts::PI * denominator * denominator.sqrt())
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(&self, ray: &Ray, hit_record: &HitRecord, rng: &mut Pcg32) -> Option<(Color, Ray)> {
+        // inverts the distribution of the cosine between the incoming and scattered directions
+        let g = self.anisotropy;
+        let xi = rng.random_f64(None);
+        let cosine = if g.abs() < 1e-3 {
+            1.0 - 2.0 * xi
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+            ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+        let sine = (1.0 - cosine * cosine).max(0.0).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * rng.random_f64(None);
+
+        let direction = Onb::new(ray.direction()).local(sine * phi.cos(), sine * phi.sin(), cosine);
+        let scattered = Ray::new(*hit_record.point(), direction, ray.time());
+        let attenuation = self
+            .albedo
+            .value(hit_record.u(), hit_record.v(), hit_record.point());
         Some((attenuation, scattered))
     }
+
+    fn scattering_pdf(&self, ray: &Ray, _hit_record: &HitRecord, scattered: &Ray) -> Option<f64> {
+        let cosine = ray
+            .direction()
+            .unit_vector()
+            .dot(&scattered.direction().unit_vector());
+        Some(self.value(cosine))
+    }
 }
@@ -0,0 +1,316 @@
+use crate::vec3::Vec3;
+
+#[derive(Clone, Copy)]
+pub struct Matrix4 {
+    m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Self {
+        Self { m }
+    }
+
+    pub fn identity() -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Self { m }
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> f64 {
+        self.m[row][column]
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Self { m }
+    }
+
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inverse = Self::identity().m;
+
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|&x, &y| a[x][column].abs().total_cmp(&a[y][column].abs()))
+                .unwrap();
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let scale = 1.0 / a[column][column];
+            for j in 0..4 {
+                a[column][j] *= scale;
+                inverse[column][j] *= scale;
+            }
+
+            for row in 0..4 {
+                if row != column {
+                    let factor = a[row][column];
+                    for j in 0..4 {
+                        a[row][j] -= factor * a[column][j];
+                        inverse[row][j] -= factor * inverse[column][j];
+                    }
+                }
+            }
+        }
+
+        Some(Self { m: inverse })
+    }
+
+    pub fn transform_point(&self, point: &Vec3) -> Vec3 {
+        let m = &self.m;
+        let x = m[0][0] * point.x() + m[0][1] * point.y() + m[0][2] * point.z() + m[0][3];
+        let y = m[1][0] * point.x() + m[1][1] * point.y() + m[1][2] * point.z() + m[1][3];
+        let z = m[2][0] * point.x() + m[2][1] * point.y() + m[2][2] * point.z() + m[2][3];
+        let w = m[3][0] * point.x() + m[3][1] * point.y() + m[3][2] * point.z() + m[3][3];
+
+        if w == 1.0 {
+            Vec3::new(x, y, z)
+        } else {
+            Vec3::new(x, y, z) / w
+        }
+    }
+
+    pub fn transform_vector(&self, vector: &Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * vector.x() + m[0][1] * vector.y() + m[0][2] * vector.z(),
+            m[1][0] * vector.x() + m[1][1] * vector.y() + m[1][2] * vector.z(),
+            m[2][0] * vector.x() + m[2][1] * vector.y() + m[2][2] * vector.z(),
+        )
+    }
+}
+
+impl std::ops::Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Matrix4 { m }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        }
+    }
+
+    pub fn from_matrix(matrix: Matrix4) -> Option<Self> {
+        Some(Self {
+            matrix,
+            inverse: matrix.inverse()?,
+        })
+    }
+
+    pub fn translate(offset: Vec3) -> Self {
+        let translation = |offset: Vec3| {
+            Matrix4::new([
+                [1.0, 0.0, 0.0, offset.x()],
+                [0.0, 1.0, 0.0, offset.y()],
+                [0.0, 0.0, 1.0, offset.z()],
+                [0.0, 0.0, 0.0, 1.0],
+            ])
+        };
+
+        Self {
+            matrix: translation(offset),
+            inverse: translation(-offset),
+        }
+    }
+
+    // None when a factor is zero, since the scaling would not be invertible.
+    pub fn scale(factors: Vec3) -> Option<Self> {
+        if factors.x() == 0.0 || factors.y() == 0.0 || factors.z() == 0.0 {
+            return None;
+        }
+
+        let scaling = |x: f64, y: f64, z: f64| {
+            Matrix4::new([
+                [x, 0.0, 0.0, 0.0],
+                [0.0, y, 0.0, 0.0],
+                [0.0, 0.0, z, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ])
+        };
+
+        Some(Self {
+            matrix: scaling(factors.x(), factors.y(), factors.z()),
+            inverse: scaling(1.0 / factors.x(), 1.0 / factors.y(), 1.0 / factors.z()),
+        })
+    }
+
+    pub fn rotate(axis: &Vec3, degrees: f64) -> Self {
+        let axis = axis.unit_vector();
+        let (x, y, z) = (axis.x(), axis.y(), axis.z());
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+
+        let matrix = Matrix4::new([
+            [
+                t * x * x + cos,
+                t * x * y - sin * z,
+                t * x * z + sin * y,
+                0.0,
+            ],
+            [
+                t * x * y + sin * z,
+                t * y * y + cos,
+                t * y * z - sin * x,
+                0.0,
+            ],
+            [
+                t * x * z - sin * y,
+                t * y * z + sin * x,
+                t * z * z + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        Self {
+            matrix,
+            inverse: matrix.transpose(),
+        }
+    }
+
+    pub fn then(&self, next: &Transform) -> Self {
+        Self {
+            matrix: next.matrix * self.matrix,
+            inverse: self.inverse * next.inverse,
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+
+    pub fn point(&self, point: &Vec3) -> Vec3 {
+        self.matrix.transform_point(point)
+    }
+
+    pub fn vector(&self, vector: &Vec3) -> Vec3 {
+        self.matrix.transform_vector(vector)
+    }
+
+    pub fn normal(&self, normal: &Vec3) -> Vec3 {
+        self.inverse.transpose().transform_vector(normal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &Vec3, b: &Vec3) {
+        let error = (*a - *b).length();
+        assert!(
+            error < 1e-9,
+            "({}, {}, {}) != ({}, {}, {})",
+            a.x(),
+            a.y(),
+            a.z(),
+            b.x(),
+            b.y(),
+            b.z()
+        );
+    }
+
+    #[test]
+    fn matrices_times_their_inverse_are_the_identity() {
+        let m = Matrix4::new([
+            [2.0, 0.5, -1.0, 3.0],
+            [0.0, 0.0, 4.0, -2.0],
+            [1.0, 3.0, 0.5, 0.0],
+            [0.25, 0.0, 0.0, 1.0],
+        ]);
+        for product in [m * m.inverse().unwrap(), m.inverse().unwrap() * m] {
+            for row in 0..4 {
+                for column in 0..4 {
+                    let identity = if row == column { 1.0 } else { 0.0 };
+                    assert!((product.get(row, column) - identity).abs() < 1e-12);
+                }
+            }
+        }
+
+        let singular = Matrix4::new([
+            [1.0, 2.0, 3.0, 0.0],
+            [2.0, 4.0, 6.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        assert!(singular.inverse().is_none());
+    }
+
+    #[test]
+    fn rotations_turn_counterclockwise_about_their_axis() {
+        let quarter = Transform::rotate(&Vec3::new(0.0, 0.0, 2.0), 90.0);
+        assert_close(
+            &quarter.point(&Vec3::new(1.0, 0.0, 3.0)),
+            &Vec3::new(0.0, 1.0, 3.0),
+        );
+        assert_close(
+            &quarter.inverse().point(&Vec3::new(0.0, 1.0, 3.0)),
+            &Vec3::new(1.0, 0.0, 3.0),
+        );
+
+        let third = Transform::rotate(&Vec3::new(1.0, 1.0, 1.0), 120.0);
+        assert_close(
+            &third.vector(&Vec3::new(1.0, 0.0, 0.0)),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+        assert_close(
+            &third
+                .then(&third)
+                .then(&third)
+                .point(&Vec3::new(0.3, -2.0, 5.0)),
+            &Vec3::new(0.3, -2.0, 5.0),
+        );
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_non_uniform_scale() {
+        let transform = Transform::scale(Vec3::new(4.0, 0.5, 1.0))
+            .unwrap()
+            .then(&Transform::rotate(&Vec3::new(1.0, 2.0, 0.5), 35.0))
+            .then(&Transform::translate(Vec3::new(1.0, -3.0, 2.0)));
+
+        // a surface with normal (1, 1, 1), spanned by two tangents
+        let normal = Vec3::new(1.0, 1.0, 1.0);
+        for tangent in [Vec3::new(1.0, -1.0, 0.0), Vec3::new(0.0, 1.0, -1.0)] {
+            let tangent = transform.vector(&tangent);
+            assert!(transform.normal(&normal).dot(&tangent).abs() < 1e-12);
+            // transforming the normal as a vector would tilt it
+            assert!(transform.vector(&normal).dot(&tangent).abs() > 0.1);
+        }
+    }
+}
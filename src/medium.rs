@@ -0,0 +1,194 @@
+use crate::aabb::Aabb;
+use crate::grid::VoxelGrid;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::number::Pcg32;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// A volume of constant density filling a closed boundary, which rays pass through or scatter
+// inside with a probability that grows with the distance they travel through it.
+pub struct ConstantMedium {
+    boundary: std::sync::Arc<dyn Hittable>,
+    negative_inverse_density: f64,
+    phase_function: std::sync::Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: std::sync::Arc<dyn Hittable>,
+        density: f64,
+        phase_function: std::sync::Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            boundary,
+            negative_inverse_density: -1.0 / density,
+            phase_function,
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let entry = self
+            .boundary
+            .hit(ray, f64::NEG_INFINITY, f64::INFINITY)?
+            .t();
+        let exit = self.boundary.hit(ray, entry + 0.0001, f64::INFINITY)?.t();
+
+        let entry = entry.max(t_min).max(0.0);
+        let exit = exit.min(t_max);
+        if entry >= exit {
+            return None;
+        }
+
+        // `hit` is not handed the sample's generator, and a medium is hit again with the same ray
+        // whenever the bvh narrows the interval or a light is sampled through it. Drawing the
+        // scattering distance from the ray itself makes every such call agree, and since the ray
+        // comes from the sample's generator, renders still do not depend on threads or tiles.
+        let mut rng = Pcg32::hashed(&ray_bits(ray));
+        let length = ray.direction().length();
+        let distance_inside = (exit - entry) * length;
+        let hit_distance = self.negative_inverse_density * (1.0 - rng.random_f64(None)).ln();
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let t = entry + hit_distance / length;
+        Some(HitRecord::new(
+            ray,
+            // arbitrary, since the phase function scatters the same way in every direction
+            Vec3::new(1.0, 0.0, 0.0),
+            ray.at(t),
+            t,
+            self.phase_function.clone(),
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+}
+
+// A volume with densities from a voxel grid stretched over an axis-aligned box. It is not a
+// hittable, since tracking a ray through it takes many random numbers: the renderer samples where
+// rays scatter inside it by delta tracking and how much light passes through it by ratio tracking.
+pub struct GridMedium {
+    grid: std::sync::Arc<VoxelGrid>,
+    minimum: Vec3,
+    maximum: Vec3,
+    density: f64,
+    phase_function: std::sync::Arc<dyn Material>,
+}
+
+impl GridMedium {
+    // `density` scales the values in the grid.
+    pub fn new(
+        grid: std::sync::Arc<VoxelGrid>,
+        a: Vec3,
+        b: Vec3,
+        density: f64,
+        phase_function: std::sync::Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            grid,
+            minimum: Vec3::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z())),
+            maximum: Vec3::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z())),
+            density,
+            phase_function,
+        }
+    }
+
+    // Samples the first point in (`t_min`, `t_max`) where the ray scatters, if any. Tentative
+    // collisions are drawn against the densest voxel and accepted in proportion to the density
+    // actually found there (delta tracking).
+    pub fn sample(&self, ray: &Ray, t_min: f64, t_max: f64, rng: &mut Pcg32) -> Option<HitRecord> {
+        let (mut t, exit) = self.interval(ray, t_min, t_max)?;
+        let majorant = self.density * self.grid.maximum() * ray.direction().length();
+        if majorant <= 0.0 {
+            return None;
+        }
+
+        loop {
+            t -= (1.0 - rng.random_f64(None)).ln() / majorant;
+            if t >= exit {
+                return None;
+            }
+
+            let point = ray.at(t);
+            if rng.random_f64(None) * self.density * self.grid.maximum() < self.density_at(&point) {
+                return Some(HitRecord::new(
+                    ray,
+                    -ray.direction().unit_vector(),
+                    point,
+                    t,
+                    self.phase_function.clone(),
+                ));
+            }
+        }
+    }
+
+    // An unbiased estimate of the fraction of light passing through between `t_min` and `t_max`,
+    // weighting down by the chance of scattering at each tentative collision (ratio tracking).
+    pub fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64, rng: &mut Pcg32) -> f64 {
+        let (mut t, exit) = match self.interval(ray, t_min, t_max) {
+            Some(interval) => interval,
+            None => return 1.0,
+        };
+        let maximum = self.density * self.grid.maximum();
+        let majorant = maximum * ray.direction().length();
+        if majorant <= 0.0 {
+            return 1.0;
+        }
+
+        let mut transmittance = 1.0;
+        loop {
+            t -= (1.0 - rng.random_f64(None)).ln() / majorant;
+            if t >= exit {
+                return transmittance;
+            }
+            transmittance *= 1.0 - self.density_at(&ray.at(t)) / maximum;
+            if transmittance <= 0.0 {
+                return 0.0;
+            }
+        }
+    }
+
+    fn density_at(&self, point: &Vec3) -> f64 {
+        let local = |k: usize| (point[k] - self.minimum[k]) / (self.maximum[k] - self.minimum[k]);
+        self.density * self.grid.density(&Vec3::new(local(0), local(1), local(2)))
+    }
+
+    // The part of (`t_min`, `t_max`) in which the ray is inside the box.
+    fn interval(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let (mut entry, mut exit) = (t_min, t_max);
+        for k in 0..3 {
+            let inverse = 1.0 / ray.direction()[k];
+            let mut t0 = (self.minimum[k] - ray.origin()[k]) * inverse;
+            let mut t1 = (self.maximum[k] - ray.origin()[k]) * inverse;
+            if inverse < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            entry = entry.max(t0);
+            exit = exit.min(t1);
+        }
+        if entry < exit {
+            Some((entry, exit))
+        } else {
+            None
+        }
+    }
+}
+
+fn ray_bits(ray: &Ray) -> [u64; 7] {
+    let (origin, direction) = (ray.origin(), ray.direction());
+    [
+        origin.x().to_bits(),
+        origin.y().to_bits(),
+        origin.z().to_bits(),
+        direction.x().to_bits(),
+        direction.y().to_bits(),
+        direction.z().to_bits(),
+        ray.time().to_bits(),
+    ]
+}
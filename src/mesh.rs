@@ -0,0 +1,157 @@
+use crate::aabb::Aabb;
+use crate::bvh::BvhNode;
+use crate::hittable::{intersect_triangle, triangle_hit_record, HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+pub struct MeshData {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f64, f64)>,
+}
+
+impl MeshData {
+    pub fn new(positions: Vec<Vec3>, normals: Vec<Vec3>, uvs: Vec<(f64, f64)>) -> Self {
+        Self {
+            positions,
+            normals,
+            uvs,
+        }
+    }
+}
+
+pub struct MeshFace {
+    positions: [usize; 3],
+    normals: Option<[usize; 3]>,
+    uvs: Option<[usize; 3]>,
+    group: usize,
+    material: std::sync::Arc<dyn Material>,
+}
+
+impl MeshFace {
+    pub fn new(
+        positions: [usize; 3],
+        normals: Option<[usize; 3]>,
+        uvs: Option<[usize; 3]>,
+        group: usize,
+        material: std::sync::Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            positions,
+            normals,
+            uvs,
+            group,
+            material,
+        }
+    }
+}
+
+pub struct Mesh {
+    groups: Vec<String>,
+    face_groups: Vec<usize>,
+    bvh: BvhNode,
+}
+
+impl Mesh {
+    pub fn new(data: MeshData, faces: Vec<MeshFace>, groups: Vec<String>) -> Self {
+        let data = std::sync::Arc::new(data);
+        let face_groups = faces.iter().map(|face| face.group).collect();
+
+        let triangles = faces
+            .into_iter()
+            .map(|face| {
+                let triangle: std::sync::Arc<dyn Hittable> = std::sync::Arc::new(MeshTriangle {
+                    data: data.clone(),
+                    face,
+                });
+                triangle
+            })
+            .collect();
+
+        Self {
+            groups,
+            face_groups,
+            bvh: BvhNode::new(triangles),
+        }
+    }
+
+    pub fn groups(&self) -> &[String] {
+        &self.groups
+    }
+
+    // The index into `groups` of the group of each face, in the order the faces were given.
+    pub fn face_groups(&self) -> &[usize] {
+        &self.face_groups
+    }
+
+    pub fn face_count(&self) -> usize {
+        self.face_groups.len()
+    }
+
+    pub fn faces_in_group(&self, group: &str) -> usize {
+        match self.groups.iter().position(|name| name == group) {
+            Some(index) => self.face_groups.iter().filter(|&&g| g == index).count(),
+            None => 0,
+        }
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.bvh.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bvh.bounding_box()
+    }
+}
+
+struct MeshTriangle {
+    data: std::sync::Arc<MeshData>,
+    face: MeshFace,
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> [Vec3; 3] {
+        let [a, b, c] = self.face.positions;
+        [
+            self.data.positions[a],
+            self.data.positions[b],
+            self.data.positions[c],
+        ]
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let vertices = self.vertices();
+        let (t, barycentric) = intersect_triangle(ray, &vertices, t_min, t_max)?;
+
+        let normals = self.face.normals.map(|[a, b, c]| {
+            [
+                self.data.normals[a],
+                self.data.normals[b],
+                self.data.normals[c],
+            ]
+        });
+        let uvs = self
+            .face
+            .uvs
+            .map(|[a, b, c]| [self.data.uvs[a], self.data.uvs[b], self.data.uvs[c]]);
+
+        Some(triangle_hit_record(
+            ray,
+            t,
+            barycentric,
+            &vertices,
+            normals.as_ref(),
+            uvs.as_ref(),
+            self.face.material.clone(),
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::from_points(&self.vertices()))
+    }
+}
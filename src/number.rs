@@ -1,7 +1,59 @@
-use rand::Rng;
+const MULTIPLIER: u64 = 6364136223846793005;
 
-pub fn random_f64(min_max: Option<(f64, f64)>) -> f64 {
-    let (min, max) = min_max.unwrap_or((0.0, 1.0));
-    let mut rng = rand::thread_rng();
-    rng.gen_range(min..=max)
+#[derive(Clone)]
+pub struct Pcg32 {
+    state: u64,
+    increment: u64,
+}
+
+impl Pcg32 {
+    pub fn new(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn for_sample(seed: u64, pixel: u64, sample: u64) -> Self {
+        Self::new(split_mix_64(seed ^ split_mix_64(sample)), pixel)
+    }
+
+    // A generator seeded by hashing `values`, for code that needs random numbers but is not handed a
+    // generator, such as intersecting a ray with a volume.
+    pub fn hashed(values: &[u64]) -> Self {
+        let seed = values
+            .iter()
+            .fold(0, |hash, value| split_mix_64(hash ^ value));
+        Self::new(seed, 0)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let state = self.state;
+        self.state = state.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+
+        let xor_shifted = (((state >> 18) ^ state) >> 27) as u32;
+        let rotation = (state >> 59) as u32;
+        xor_shifted.rotate_right(rotation)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    pub fn random_f64(&mut self, min_max: Option<(f64, f64)>) -> f64 {
+        let (min, max) = min_max.unwrap_or((0.0, 1.0));
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        min + (max - min) * unit
+    }
+}
+
+fn split_mix_64(value: u64) -> u64 {
+    let value = value.wrapping_add(0x9e3779b97f4a7c15);
+    let value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    let value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+    value ^ (value >> 31)
 }
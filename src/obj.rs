@@ -0,0 +1,470 @@
+use crate::color::Color;
+use crate::material::{Dielectric, Lambertian, Material, Metal};
+use crate::mesh::{Mesh, MeshData, MeshFace};
+use crate::vec3::Vec3;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(PathBuf, std::io::Error),
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl Display for ObjError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            ObjError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+pub fn load(path: &Path, default_material: std::sync::Arc<dyn Material>) -> Result<Mesh, ObjError> {
+    let source =
+        std::fs::read_to_string(path).map_err(|error| ObjError::Io(path.to_path_buf(), error))?;
+    parse(&source, path, default_material)
+}
+
+// Parses the contents of the OBJ file at `path`, which names the file in errors and locates
+// material libraries.
+fn parse(
+    source: &str,
+    path: &Path,
+    default_material: std::sync::Arc<dyn Material>,
+) -> Result<Mesh, ObjError> {
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut faces = Vec::new();
+    let mut groups = vec![String::from("default")];
+    let mut group = 0;
+    let mut materials = HashMap::new();
+    let mut material = default_material;
+
+    for (index, line) in source.lines().enumerate() {
+        let error = |message: String| ObjError::Parse {
+            path: path.to_path_buf(),
+            line: index + 1,
+            message,
+        };
+
+        let mut tokens = line.split('#').next().unwrap().split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => positions.push(parse_vec3(&arguments).map_err(error)?),
+            "vn" => normals.push(parse_vec3(&arguments).map_err(error)?),
+            "vt" => {
+                let u = parse_f64(arguments.first()).map_err(error)?;
+                let v = match arguments.get(1) {
+                    Some(_) => parse_f64(arguments.get(1)).map_err(error)?,
+                    None => 0.0,
+                };
+                uvs.push((u, v));
+            }
+            "f" => {
+                if arguments.len() < 3 {
+                    return Err(error(format!(
+                        "face needs at least 3 vertices, found {}",
+                        arguments.len()
+                    )));
+                }
+
+                let vertices = arguments
+                    .iter()
+                    .map(|argument| {
+                        parse_face_vertex(argument, positions.len(), uvs.len(), normals.len())
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+
+                for i in 1..vertices.len() - 1 {
+                    let corners = [vertices[0], vertices[i], vertices[i + 1]];
+                    faces.push(MeshFace::new(
+                        [corners[0].0, corners[1].0, corners[2].0],
+                        match (corners[0].2, corners[1].2, corners[2].2) {
+                            (Some(a), Some(b), Some(c)) => Some([a, b, c]),
+                            _ => None,
+                        },
+                        match (corners[0].1, corners[1].1, corners[2].1) {
+                            (Some(a), Some(b), Some(c)) => Some([a, b, c]),
+                            _ => None,
+                        },
+                        group,
+                        material.clone(),
+                    ));
+                }
+            }
+            "g" | "o" => {
+                let name = arguments.join(" ");
+                group = match groups.iter().position(|g| g == &name) {
+                    Some(index) => index,
+                    None => {
+                        groups.push(name);
+                        groups.len() - 1
+                    }
+                };
+            }
+            "mtllib" => {
+                for library in &arguments {
+                    materials.extend(load_mtl(&directory.join(library))?);
+                }
+            }
+            "usemtl" => {
+                let name = arguments.join(" ");
+                material = materials
+                    .get(&name)
+                    .cloned()
+                    .ok_or_else(|| error(format!("unknown material '{}'", name)))?;
+            }
+            _ => {}
+        }
+    }
+
+    if faces.is_empty() {
+        return Err(ObjError::Parse {
+            path: path.to_path_buf(),
+            line: source.lines().count(),
+            message: String::from("no faces"),
+        });
+    }
+
+    Ok(Mesh::new(
+        MeshData::new(positions, normals, uvs),
+        faces,
+        groups,
+    ))
+}
+
+pub fn load_mtl(path: &Path) -> Result<HashMap<String, std::sync::Arc<dyn Material>>, ObjError> {
+    let source =
+        std::fs::read_to_string(path).map_err(|error| ObjError::Io(path.to_path_buf(), error))?;
+    parse_mtl(&source, path)
+}
+
+fn parse_mtl(
+    source: &str,
+    path: &Path,
+) -> Result<HashMap<String, std::sync::Arc<dyn Material>>, ObjError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let error = |message: String| ObjError::Parse {
+            path: path.to_path_buf(),
+            line: index + 1,
+            message,
+        };
+
+        let mut tokens = line.split('#').next().unwrap().split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let arguments: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if let Some((name, material)) = current.take() {
+                materials.insert(name, material.build());
+            }
+            current = Some((arguments.join(" "), MtlMaterial::default()));
+            continue;
+        }
+
+        let material = match current.as_mut() {
+            Some((_, material)) => material,
+            None => match keyword {
+                "Kd" | "Ks" | "Ns" | "Ni" | "d" | "Tr" | "illum" => {
+                    return Err(error(format!("'{}' before 'newmtl'", keyword)))
+                }
+                _ => continue,
+            },
+        };
+
+        match keyword {
+            "Kd" => material.diffuse = parse_vec3(&arguments).map_err(error)?,
+            "Ks" => material.specular = parse_vec3(&arguments).map_err(error)?,
+            "Ns" => material.specular_exponent = parse_f64(arguments.first()).map_err(error)?,
+            "Ni" => material.index_of_refraction = parse_f64(arguments.first()).map_err(error)?,
+            "d" => material.dissolve = parse_f64(arguments.first()).map_err(error)?,
+            "Tr" => material.dissolve = 1.0 - parse_f64(arguments.first()).map_err(error)?,
+            "illum" => {
+                material.illumination = arguments
+                    .first()
+                    .and_then(|argument| argument.parse().ok())
+                    .ok_or_else(|| error(String::from("expected an illumination model")))?
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((name, material)) = current.take() {
+        materials.insert(name, material.build());
+    }
+
+    Ok(materials)
+}
+
+struct MtlMaterial {
+    diffuse: Vec3,
+    specular: Vec3,
+    specular_exponent: f64,
+    index_of_refraction: f64,
+    dissolve: f64,
+    illumination: usize,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            diffuse: Vec3::new(0.8, 0.8, 0.8),
+            specular: Vec3::new(0.0, 0.0, 0.0),
+            specular_exponent: 0.0,
+            index_of_refraction: 1.0,
+            dissolve: 1.0,
+            illumination: 2,
+        }
+    }
+}
+
+impl MtlMaterial {
+    fn build(&self) -> std::sync::Arc<dyn Material> {
+        let transparent = self.dissolve < 1.0 || matches!(self.illumination, 4 | 6 | 7 | 9);
+        let reflective = matches!(self.illumination, 3 | 5 | 8);
+
+        if transparent {
+            std::sync::Arc::new(Dielectric::new(self.index_of_refraction))
+        } else if reflective {
+            let albedo = if self.specular.near_zero() {
+                self.diffuse
+            } else {
+                self.specular
+            };
+            let fuzz = (1.0 - self.specular_exponent / 1000.0).clamp(0.0, 1.0);
+            std::sync::Arc::new(Metal::new(
+                Color::new(albedo.x(), albedo.y(), albedo.z()),
+                fuzz,
+            ))
+        } else {
+            std::sync::Arc::new(Lambertian::new(Color::new(
+                self.diffuse.x(),
+                self.diffuse.y(),
+                self.diffuse.z(),
+            )))
+        }
+    }
+}
+
+fn parse_f64(argument: Option<&&str>) -> Result<f64, String> {
+    let argument = argument.ok_or_else(|| String::from("expected a number"))?;
+    argument
+        .parse()
+        .map_err(|_| format!("invalid number '{}'", argument))
+}
+
+fn parse_vec3(arguments: &[&str]) -> Result<Vec3, String> {
+    Ok(Vec3::new(
+        parse_f64(arguments.first())?,
+        parse_f64(arguments.get(1))?,
+        parse_f64(arguments.get(2))?,
+    ))
+}
+
+fn parse_face_vertex(
+    argument: &str,
+    positions: usize,
+    uvs: usize,
+    normals: usize,
+) -> Result<(usize, Option<usize>, Option<usize>), String> {
+    let mut parts = argument.split('/');
+
+    let position = parse_index(parts.next(), positions)?
+        .ok_or_else(|| format!("missing vertex index in '{}'", argument))?;
+    let uv = parse_index(parts.next(), uvs)?;
+    let normal = parse_index(parts.next(), normals)?;
+
+    Ok((position, uv, normal))
+}
+
+fn parse_index(part: Option<&str>, count: usize) -> Result<Option<usize>, String> {
+    let part = match part {
+        Some(part) if !part.is_empty() => part,
+        _ => return Ok(None),
+    };
+
+    let index: isize = part
+        .parse()
+        .map_err(|_| format!("invalid index '{}'", part))?;
+    let resolved = if index < 0 {
+        count as isize + index
+    } else {
+        index - 1
+    };
+
+    if resolved < 0 || resolved as usize >= count {
+        return Err(format!("index {} out of range", index));
+    }
+
+    Ok(Some(resolved as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::Hittable;
+    use crate::ray::Ray;
+
+    const TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 0 1\nvn 0 0 1\n";
+
+    fn parse_obj(source: &str) -> Result<Mesh, ObjError> {
+        let material = std::sync::Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        parse(source, Path::new("test.obj"), material)
+    }
+
+    fn error_line(result: Result<Mesh, ObjError>) -> usize {
+        match result {
+            Err(ObjError::Parse { line, .. }) => line,
+            Err(error) => panic!("expected a parse error, found {}", error),
+            Ok(_) => panic!("expected a parse error"),
+        }
+    }
+
+    // Hits the mesh at (0.25, 0.25) from in front of the triangle.
+    fn hit(mesh: &Mesh) -> crate::hittable::HitRecord {
+        let ray = Ray::new(Vec3::new(0.25, 0.25, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        mesh.hit(&ray, 0.001, f64::INFINITY)
+            .expect("ray misses the mesh")
+    }
+
+    #[test]
+    fn faces_without_uvs_or_normals() {
+        let mesh = parse_obj(&format!("{}f 1 2 3\n", TRIANGLE)).unwrap();
+        assert_eq!(mesh.face_count(), 1);
+
+        let hit_record = hit(&mesh);
+        assert!((hit_record.t() - 1.0).abs() < 1e-9);
+        assert_eq!((hit_record.u(), hit_record.v()), (0.25, 0.25));
+    }
+
+    #[test]
+    fn faces_with_uvs_and_normals() {
+        let mesh = parse_obj(&format!("{}f 1/3/1 2/2/1 3/1/1\n", TRIANGLE)).unwrap();
+
+        // the uvs are assigned in reverse, so (0.25, 0.25) interpolates to u = 0.25, v = 0.5
+        let hit_record = hit(&mesh);
+        assert!((hit_record.u() - 0.25).abs() < 1e-9);
+        assert!((hit_record.v() - 0.5).abs() < 1e-9);
+        assert!((hit_record.normal().z() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn faces_with_normals_only() {
+        let mesh = parse_obj(&format!("{}f 1//1 2//1 3//1\n", TRIANGLE)).unwrap();
+        assert_eq!(mesh.face_count(), 1);
+        assert!((hit(&mesh).normal().z() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn negative_indices_count_back_from_the_last_vertex() {
+        let mesh = parse_obj(&format!("{}f -3/-3/-1 -2/-2/-1 -1/-1/-1\n", TRIANGLE)).unwrap();
+        let hit_record = hit(&mesh);
+        assert_eq!((hit_record.u(), hit_record.v()), (0.25, 0.25));
+    }
+
+    #[test]
+    fn polygons_are_split_into_triangles() {
+        let mesh = parse_obj("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n").unwrap();
+        assert_eq!(mesh.face_count(), 2);
+    }
+
+    #[test]
+    fn groups_are_collected() {
+        let mesh = parse_obj(&format!(
+            "{}g first\nf 1 2 3\no second\nf 1 2 3\n",
+            TRIANGLE
+        ));
+        assert_eq!(mesh.unwrap().groups(), ["default", "first", "second"]);
+    }
+
+    #[test]
+    fn faces_belong_to_the_group_they_follow() {
+        let mesh = parse_obj(&format!(
+            "{}f 1 2 3\ng first\nf 1 2 3\ng second\nf 1 2 3\ng first\nf 1 2 3\n",
+            TRIANGLE
+        ))
+        .unwrap();
+        assert_eq!(mesh.face_groups(), [0, 1, 2, 1]);
+        assert_eq!(mesh.faces_in_group("first"), 2);
+        assert_eq!(mesh.faces_in_group("default"), 1);
+        assert_eq!(mesh.faces_in_group("missing"), 0);
+    }
+
+    #[test]
+    fn texture_coordinates_default_v_to_zero() {
+        let mesh = parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.5\nf 1/1 2/1 3/1\n").unwrap();
+        assert_eq!((hit(&mesh).u(), hit(&mesh).v()), (0.5, 0.0));
+    }
+
+    #[test]
+    fn malformed_lines_are_errors() {
+        assert_eq!(error_line(parse_obj("v 0 0\n")), 1);
+        assert_eq!(error_line(parse_obj("v 0 0 0\nvt 0.5 x\n")), 2);
+        assert_eq!(error_line(parse_obj("vt x\n")), 1);
+        assert_eq!(error_line(parse_obj(&format!("{}f 1 2\n", TRIANGLE))), 8);
+        assert_eq!(error_line(parse_obj(&format!("{}f 1 2 4\n", TRIANGLE))), 8);
+        assert_eq!(error_line(parse_obj(&format!("{}f 1 2 -4\n", TRIANGLE))), 8);
+        assert_eq!(error_line(parse_obj(&format!("{}f 1 2 0\n", TRIANGLE))), 8);
+        assert_eq!(error_line(parse_obj(&format!("{}f 1 2 a\n", TRIANGLE))), 8);
+        assert_eq!(
+            error_line(parse_obj(&format!("{}f 1/4 2 3\n", TRIANGLE))),
+            8
+        );
+        assert_eq!(
+            error_line(parse_obj(&format!("{}usemtl missing\n", TRIANGLE))),
+            8
+        );
+        assert_eq!(error_line(parse_obj(TRIANGLE)), 7);
+    }
+
+    #[test]
+    fn materials_are_read_from_libraries() {
+        let materials = parse_mtl(
+            "newmtl red\nKd 1 0 0\n\nnewmtl mirror # comment\nKs 1 1 1\nillum 3\n",
+            Path::new("test.mtl"),
+        )
+        .unwrap();
+        let mut names = materials.keys().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["mirror", "red"]);
+    }
+
+    #[test]
+    fn malformed_material_lines_are_errors() {
+        let line = |source: &str| match parse_mtl(source, Path::new("test.mtl")) {
+            Err(ObjError::Parse { line, .. }) => line,
+            _ => panic!("expected a parse error"),
+        };
+        assert_eq!(line("Kd 1 0 0\n"), 1);
+        assert_eq!(line("newmtl red\nKd 1 0\n"), 2);
+        assert_eq!(line("newmtl red\nNs x\n"), 2);
+        assert_eq!(line("newmtl red\nillum\n"), 2);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::hittable::Hittable;
+use crate::number::Pcg32;
+use crate::onb::Onb;
+use crate::vec3::Vec3;
+
+pub trait Pdf {
+    fn value(&self, direction: &Vec3) -> f64;
+
+    fn generate(&self, rng: &mut Pcg32) -> Vec3;
+}
+
+pub struct CosinePdf {
+    onb: Onb,
+}
+
+impl CosinePdf {
+    pub fn new(normal: &Vec3) -> Self {
+        Self {
+            onb: Onb::new(normal),
+        }
+    }
+}
+
+impl Pdf for CosinePdf {
+    fn value(&self, direction: &Vec3) -> f64 {
+        let cosine = direction.unit_vector().dot(self.onb.w());
+        (cosine / std::f64::consts::PI).max(0.0)
+    }
+
+    fn generate(&self, rng: &mut Pcg32) -> Vec3 {
+        let r1 = rng.random_f64(None);
+        let r2 = rng.random_f64(None);
+        let phi = 2.0 * std::f64::consts::PI * r1;
+        self.onb.local(
+            phi.cos() * r2.sqrt(),
+            phi.sin() * r2.sqrt(),
+            (1.0 - r2).sqrt(),
+        )
+    }
+}
+
+pub struct HittablePdf<'a> {
+    object: &'a dyn Hittable,
+    origin: Vec3,
+}
+
+impl<'a> HittablePdf<'a> {
+    pub fn new(object: &'a dyn Hittable, origin: Vec3) -> Self {
+        Self { object, origin }
+    }
+}
+
+impl<'a> Pdf for HittablePdf<'a> {
+    fn value(&self, direction: &Vec3) -> f64 {
+        self.object.pdf_value(&self.origin, direction)
+    }
+
+    fn generate(&self, rng: &mut Pcg32) -> Vec3 {
+        self.object.random(&self.origin, rng)
+    }
+}
+
+pub struct MixturePdf<P: Pdf> {
+    pdfs: Vec<P>,
+}
+
+impl<P: Pdf> MixturePdf<P> {
+    pub fn new(pdfs: Vec<P>) -> Self {
+        Self { pdfs }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pdfs.is_empty()
+    }
+}
+
+impl<P: Pdf> Pdf for MixturePdf<P> {
+    fn value(&self, direction: &Vec3) -> f64 {
+        if self.pdfs.is_empty() {
+            return 0.0;
+        }
+
+        let sum: f64 = self.pdfs.iter().map(|pdf| pdf.value(direction)).sum();
+        sum / self.pdfs.len() as f64
+    }
+
+    fn generate(&self, rng: &mut Pcg32) -> Vec3 {
+        let index = (rng.random_f64(None) * self.pdfs.len() as f64) as usize;
+        self.pdfs[index.min(self.pdfs.len() - 1)].generate(rng)
+    }
+}
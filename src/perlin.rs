@@ -0,0 +1,115 @@
+use crate::number::Pcg32;
+use crate::vec3::Vec3;
+
+const POINT_COUNT: usize = 256;
+
+pub struct Perlin {
+    gradients: Vec<Vec3>,
+    permutation_x: Vec<usize>,
+    permutation_y: Vec<usize>,
+    permutation_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Pcg32::new(seed, 0);
+
+        let gradients = (0..POINT_COUNT)
+            .map(|_| loop {
+                let gradient = Vec3::random(Some((-1.0, 1.0)), &mut rng);
+                let length_squared = gradient.length_squared();
+                if length_squared > 1e-8 && length_squared <= 1.0 {
+                    return gradient.unit_vector();
+                }
+            })
+            .collect();
+
+        let mut permutation = || {
+            let mut permutation: Vec<usize> = (0..POINT_COUNT).collect();
+            for i in (1..POINT_COUNT).rev() {
+                let j = (rng.random_f64(None) * (i + 1) as f64) as usize;
+                permutation.swap(i, j);
+            }
+            permutation
+        };
+
+        Self {
+            gradients,
+            permutation_x: permutation(),
+            permutation_y: permutation(),
+            permutation_z: permutation(),
+        }
+    }
+
+    pub fn noise(&self, point: &Vec3) -> f64 {
+        let (x, y, z) = (point.x().floor(), point.y().floor(), point.z().floor());
+        let u = point.x() - x;
+        let v = point.y() - y;
+        let w = point.z() - z;
+        let (i, j, k) = (x as i64, y as i64, z as i64);
+
+        let mut corners = [[[Vec3::new(0.0, 0.0, 0.0); 2]; 2]; 2];
+        for (di, plane) in corners.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    let index = self.permutation_x[((i + di as i64) & 255) as usize]
+                        ^ self.permutation_y[((j + dj as i64) & 255) as usize]
+                        ^ self.permutation_z[((k + dk as i64) & 255) as usize];
+                    *corner = self.gradients[index];
+                }
+            }
+        }
+
+        Self::interpolate(&corners, u, v, w)
+    }
+
+    pub fn fbm(&self, point: &Vec3, octaves: usize) -> f64 {
+        let mut sum = 0.0;
+        let mut point = *point;
+        let mut weight = 1.0;
+
+        for _ in 0..octaves {
+            sum += weight * self.noise(&point);
+            weight *= 0.5;
+            point = 2.0 * point;
+        }
+
+        sum
+    }
+
+    pub fn turbulence(&self, point: &Vec3, octaves: usize) -> f64 {
+        let mut sum = 0.0;
+        let mut point = *point;
+        let mut weight = 1.0;
+
+        for _ in 0..octaves {
+            sum += weight * self.noise(&point).abs();
+            weight *= 0.5;
+            point = 2.0 * point;
+        }
+
+        sum
+    }
+
+    fn interpolate(corners: &[[[Vec3; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
+        let uu = u * u * (3.0 - 2.0 * u);
+        let vv = v * v * (3.0 - 2.0 * v);
+        let ww = w * w * (3.0 - 2.0 * w);
+
+        let mut accumulator = 0.0;
+        for (i, plane) in corners.iter().enumerate() {
+            for (j, row) in plane.iter().enumerate() {
+                for (k, gradient) in row.iter().enumerate() {
+                    let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                    let weight = Vec3::new(u - fi, v - fj, w - fk);
+                    accumulator += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                        * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                        * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                        * gradient.dot(&weight);
+                }
+            }
+        }
+
+        accumulator
+    }
+}
@@ -0,0 +1,223 @@
+// Terms are treated as zero when they are this small relative to the size of the polynomial's
+// other terms, so that scaling the polynomial or its variable does not change which roots are found.
+const EPSILON: f64 = 1e-12;
+
+// Real roots of a x^2 + b x + c, in no particular order. The roots are computed without
+// subtracting nearly equal numbers, so the smaller one keeps its precision.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    let scale = a.abs().max(b.abs()).max(c.abs());
+    if a.abs() <= EPSILON * scale {
+        return if b.abs() <= EPSILON * scale {
+            Vec::new()
+        } else {
+            vec![-c / b]
+        };
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return Vec::new();
+    }
+
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    if q == 0.0 {
+        return vec![0.0];
+    }
+    vec![q / a, c / q]
+}
+
+// Real roots of x^3 + a x^2 + b x + c.
+pub fn solve_cubic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    // the magnitude of the largest root is on the order of the scale
+    let scale = a.abs().max(b.abs().sqrt()).max(c.abs().cbrt());
+
+    // substitute x = y - a / 3 to get y^3 + p y + q
+    let a_squared = a * a;
+    let p = (-a_squared / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * a_squared - a * b / 3.0 + c) / 2.0;
+
+    let p_cubed = p * p * p;
+    let discriminant = q * q + p_cubed;
+
+    let roots = if discriminant.abs() <= EPSILON * scale.powi(6) {
+        if q.abs() <= EPSILON * scale.powi(3) {
+            vec![0.0]
+        } else {
+            let u = (-q).cbrt();
+            vec![2.0 * u, -u]
+        }
+    } else if discriminant < 0.0 {
+        let phi = (-q / (-p_cubed).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let t = 2.0 * (-p).sqrt();
+        vec![
+            t * phi.cos(),
+            -t * (phi + std::f64::consts::PI / 3.0).cos(),
+            -t * (phi - std::f64::consts::PI / 3.0).cos(),
+        ]
+    } else {
+        let root = discriminant.sqrt();
+        vec![(root - q).cbrt() - (root + q).cbrt()]
+    };
+
+    roots.into_iter().map(|y| y - a / 3.0).collect()
+}
+
+// Real roots of x^4 + a x^3 + b x^2 + c x + d, found with Ferrari's method and then polished with
+// a few Newton steps, since the closed form loses precision when the roots are close together.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    let scale = a
+        .abs()
+        .max(b.abs().sqrt())
+        .max(c.abs().cbrt())
+        .max(d.abs().sqrt().sqrt());
+
+    // substitute x = y - a / 4 to get y^4 + p y^2 + q y + r
+    let a_squared = a * a;
+    let p = -3.0 / 8.0 * a_squared + b;
+    let q = a_squared * a / 8.0 - a * b / 2.0 + c;
+    let r = -3.0 / 256.0 * a_squared * a_squared + a_squared * b / 16.0 - a * c / 4.0 + d;
+
+    let roots = if r.abs() <= EPSILON * scale.powi(4) {
+        // y (y^3 + p y + q) = 0
+        let mut roots = solve_cubic(0.0, p, q);
+        roots.push(0.0);
+        roots
+    } else {
+        // one real root of the resolvent cubic splits the quartic into two quadratics
+        let z = solve_cubic(-p / 2.0, -r, r * p / 2.0 - q * q / 8.0)[0];
+
+        // `x` is of the order of `scale` to the power `degree`
+        let square_root = |x: f64, degree: i32| {
+            if x.abs() <= EPSILON * scale.powi(degree) {
+                Some(0.0)
+            } else if x > 0.0 {
+                Some(x.sqrt())
+            } else {
+                None
+            }
+        };
+        let (u, v) = match (square_root(z * z - r, 4), square_root(2.0 * z - p, 2)) {
+            (Some(u), Some(v)) => (u, v),
+            _ => return Vec::new(),
+        };
+        let v = if q < 0.0 { -v } else { v };
+
+        let mut roots = solve_quadratic(1.0, v, z - u);
+        roots.extend(solve_quadratic(1.0, -v, z + u));
+        roots
+    };
+
+    roots
+        .into_iter()
+        .map(|y| {
+            let mut x = y - a / 4.0;
+            for _ in 0..3 {
+                let value = (((x + a) * x + b) * x + c) * x + d;
+                let derivative = ((4.0 * x + 3.0 * a) * x + 2.0 * b) * x + c;
+                if derivative == 0.0 {
+                    break;
+                }
+                x -= value / derivative;
+            }
+            x
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every expected root is found and every root found is expected, ignoring multiplicity.
+    fn assert_roots(roots: Vec<f64>, expected: &[f64]) {
+        let near = |x: f64, y: f64| (x - y).abs() < 1e-6 * (1.0 + y.abs());
+        assert!(
+            expected.iter().all(|&e| roots.iter().any(|&r| near(r, e)))
+                && roots.iter().all(|&r| expected.iter().any(|&e| near(r, e))),
+            "found {:?}, expected {:?}",
+            roots,
+            expected
+        );
+    }
+
+    #[test]
+    fn quadratic_roots() {
+        assert_roots(solve_quadratic(1.0, -3.0, 2.0), &[1.0, 2.0]);
+        assert_roots(solve_quadratic(2.0, 0.0, -8.0), &[-2.0, 2.0]);
+        assert_roots(solve_quadratic(1.0, -2.0, 1.0), &[1.0]);
+        assert_roots(solve_quadratic(1.0, 0.0, 1.0), &[]);
+        assert_roots(solve_quadratic(0.0, 2.0, -4.0), &[2.0]);
+        assert_roots(solve_quadratic(0.0, 0.0, 1.0), &[]);
+    }
+
+    #[test]
+    fn quadratic_roots_do_not_depend_on_scale() {
+        assert_roots(solve_quadratic(1e-12, -3e-12, 2e-12), &[1.0, 2.0]);
+        assert_roots(solve_quadratic(1e12, -3e12, 2e12), &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn cubic_with_one_real_root() {
+        // (x - 1) (x^2 + 1)
+        assert_roots(solve_cubic(-1.0, 1.0, -1.0), &[1.0]);
+    }
+
+    #[test]
+    fn cubic_with_three_real_roots() {
+        // (x - 1) (x - 2) (x + 3)
+        assert_roots(solve_cubic(0.0, -7.0, 6.0), &[1.0, 2.0, -3.0]);
+        // (x - 1) (x - 2) (x - 4)
+        assert_roots(solve_cubic(-7.0, 14.0, -8.0), &[1.0, 2.0, 4.0]);
+    }
+
+    #[test]
+    fn cubic_with_repeated_roots() {
+        // (x - 1)^2 (x + 2)
+        assert_roots(solve_cubic(0.0, -3.0, 2.0), &[1.0, -2.0]);
+        // (x - 2)^3
+        assert_roots(solve_cubic(-6.0, 12.0, -8.0), &[2.0]);
+    }
+
+    #[test]
+    fn quartic_with_four_real_roots() {
+        // (x + 1) (x - 1) (x - 2) (x - 3)
+        assert_roots(solve_quartic(-5.0, 5.0, 5.0, -6.0), &[-1.0, 1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn quartic_with_two_real_roots() {
+        // (x - 1) (x - 2) (x^2 + 1)
+        assert_roots(solve_quartic(-3.0, 3.0, -3.0, 2.0), &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn quartic_without_real_roots() {
+        // (x^2 + 1) (x^2 + 4)
+        assert_roots(solve_quartic(0.0, 5.0, 0.0, 4.0), &[]);
+    }
+
+    #[test]
+    fn quartic_with_double_roots() {
+        // (x - 1)^2 (x - 3)^2, as for a ray grazing the top of a torus
+        assert_roots(solve_quartic(-8.0, 22.0, -24.0, 9.0), &[1.0, 3.0]);
+        // (x - 1)^2 (x - 2) (x - 4)
+        assert_roots(solve_quartic(-8.0, 21.0, -22.0, 8.0), &[1.0, 2.0, 4.0]);
+    }
+
+    #[test]
+    fn quartic_with_a_root_at_the_depressed_origin() {
+        // x (x - 1) (x - 2) (x - 5) has the root 2 = -a / 4, so r = 0
+        assert_roots(solve_quartic(-8.0, 17.0, -10.0, 0.0), &[0.0, 1.0, 2.0, 5.0]);
+    }
+
+    #[test]
+    fn quartic_roots_do_not_depend_on_scale() {
+        // (x + 1) (x - 1) (x - 2) (x - 3) with x = 1000 y
+        assert_roots(solve_quartic(-5e3, 5e6, 5e9, -6e12), &[-1e3, 1e3, 2e3, 3e3]);
+        // and with x = y / 1000
+        assert_roots(
+            solve_quartic(-5e-3, 5e-6, 5e-9, -6e-12),
+            &[-1e-3, 1e-3, 2e-3, 3e-3],
+        );
+    }
+}
@@ -0,0 +1,514 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::number::Pcg32;
+use crate::onb::Onb;
+use crate::polynomial::{solve_quadratic, solve_quartic};
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// Shapes are intersected in a local frame with the shape's axis along z. The frame is orthonormal,
+// so a ray keeps its parameter t in local coordinates, and right-handed, so that angles around the
+// axis run counterclockwise.
+struct Frame {
+    origin: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+}
+
+impl Frame {
+    fn new(origin: Vec3, axis: Vec3) -> Self {
+        let onb = Onb::new(&axis);
+        let w = *onb.w();
+        let u = *onb.u();
+        Self {
+            origin,
+            u,
+            v: w.cross(&u),
+            w,
+        }
+    }
+
+    fn local_point(&self, point: &Vec3) -> Vec3 {
+        self.local_vector(&(point - self.origin))
+    }
+
+    fn local_vector(&self, vector: &Vec3) -> Vec3 {
+        Vec3::new(
+            vector.dot(&self.u),
+            vector.dot(&self.v),
+            vector.dot(&self.w),
+        )
+    }
+
+    fn world_point(&self, point: &Vec3) -> Vec3 {
+        self.origin + self.world_vector(point)
+    }
+
+    fn world_vector(&self, vector: &Vec3) -> Vec3 {
+        vector.x() * self.u + vector.y() * self.v + vector.z() * self.w
+    }
+
+    // Half the size along each world axis of a circle of `radius` around the frame's axis.
+    fn extent(&self, radius: f64) -> Vec3 {
+        let half = |k: f64| radius * (1.0 - k * k).max(0.0).sqrt();
+        Vec3::new(half(self.w.x()), half(self.w.y()), half(self.w.z()))
+    }
+
+    fn hit_record(
+        &self,
+        ray: &Ray,
+        hit: LocalHit,
+        material: &std::sync::Arc<dyn Material>,
+    ) -> HitRecord {
+        let outward_normal = self.world_vector(&hit.normal);
+        HitRecord::new(ray, outward_normal, ray.at(hit.t), hit.t, material.clone())
+            .with_uv(hit.u, hit.v)
+    }
+}
+
+struct LocalHit {
+    t: f64,
+    normal: Vec3,
+    u: f64,
+    v: f64,
+}
+
+fn nearest(hits: Vec<LocalHit>) -> Option<LocalHit> {
+    hits.into_iter()
+        .min_by(|a, b| a.t.partial_cmp(&b.t).unwrap())
+}
+
+// The angle of a local point around the z axis, mapped to [0, 1].
+fn angle(point: &Vec3) -> f64 {
+    (point.y().atan2(point.x()) + std::f64::consts::PI) / (2.0 * std::f64::consts::PI)
+}
+
+// Where a local ray crosses the plane z = `height` between two distances from the z axis.
+fn hit_ring(
+    origin: &Vec3,
+    direction: &Vec3,
+    height: f64,
+    inner_radius: f64,
+    outer_radius: f64,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, Vec3)> {
+    if direction.z().abs() < 1e-8 {
+        return None;
+    }
+
+    let t = (height - origin.z()) / direction.z();
+    if !(t_min..=t_max).contains(&t) {
+        return None;
+    }
+
+    let point = origin + t * direction;
+    let distance_squared = point.x().powi(2) + point.y().powi(2);
+    if distance_squared < inner_radius.powi(2) || outer_radius.powi(2) < distance_squared {
+        return None;
+    }
+
+    Some((t, point))
+}
+
+// A cap of radius `radius` at `height`, with planar texture coordinates.
+fn hit_cap(
+    origin: &Vec3,
+    direction: &Vec3,
+    height: f64,
+    radius: f64,
+    normal: f64,
+    t_min: f64,
+    t_max: f64,
+) -> Option<LocalHit> {
+    let (t, point) = hit_ring(origin, direction, height, 0.0, radius, t_min, t_max)?;
+    Some(LocalHit {
+        t,
+        normal: Vec3::new(0.0, 0.0, normal),
+        u: 0.5 * (point.x() / radius + 1.0),
+        v: 0.5 * (point.y() / radius + 1.0),
+    })
+}
+
+// A cylinder from `base` to `top`, open at both ends unless it has caps. Its u coordinate runs
+// around the axis and v along it.
+pub struct Cylinder {
+    frame: Frame,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    material: std::sync::Arc<dyn Material>,
+}
+
+impl Cylinder {
+    pub fn new(base: Vec3, top: Vec3, radius: f64, material: std::sync::Arc<dyn Material>) -> Self {
+        Self {
+            frame: Frame::new(base, top - base),
+            radius,
+            height: (top - base).length(),
+            capped: false,
+            material,
+        }
+    }
+
+    pub fn with_caps(mut self) -> Self {
+        self.capped = true;
+        self
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let origin = self.frame.local_point(ray.origin());
+        let direction = self.frame.local_vector(ray.direction());
+
+        let mut hits = solve_quadratic(
+            direction.x().powi(2) + direction.y().powi(2),
+            2.0 * (origin.x() * direction.x() + origin.y() * direction.y()),
+            origin.x().powi(2) + origin.y().powi(2) - self.radius.powi(2),
+        )
+        .into_iter()
+        .filter(|t| (t_min..=t_max).contains(t))
+        .filter_map(|t| {
+            let point = origin + t * direction;
+            if !(0.0..=self.height).contains(&point.z()) {
+                return None;
+            }
+            Some(LocalHit {
+                t,
+                normal: Vec3::new(point.x() / self.radius, point.y() / self.radius, 0.0),
+                u: angle(&point),
+                v: point.z() / self.height,
+            })
+        })
+        .collect::<Vec<_>>();
+
+        if self.capped {
+            let caps = [(0.0, -1.0), (self.height, 1.0)];
+            hits.extend(caps.iter().filter_map(|&(height, normal)| {
+                hit_cap(
+                    &origin,
+                    &direction,
+                    height,
+                    self.radius,
+                    normal,
+                    t_min,
+                    t_max,
+                )
+            }));
+        }
+
+        nearest(hits).map(|hit| self.frame.hit_record(ray, hit, &self.material))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let base = self.frame.origin;
+        let top = self.frame.world_point(&Vec3::new(0.0, 0.0, self.height));
+        let extent = self.frame.extent(self.radius);
+        Some(Aabb::from_points(&[
+            base - extent,
+            base + extent,
+            top - extent,
+            top + extent,
+        ]))
+    }
+}
+
+// A cone with a circular base of `radius` narrowing to `apex`, open at the base unless it has a
+// cap. Its u coordinate runs around the axis and v from the base to the apex.
+pub struct Cone {
+    frame: Frame,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    material: std::sync::Arc<dyn Material>,
+}
+
+impl Cone {
+    pub fn new(
+        base: Vec3,
+        apex: Vec3,
+        radius: f64,
+        material: std::sync::Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            frame: Frame::new(base, apex - base),
+            radius,
+            height: (apex - base).length(),
+            capped: false,
+            material,
+        }
+    }
+
+    pub fn with_caps(mut self) -> Self {
+        self.capped = true;
+        self
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let origin = self.frame.local_point(ray.origin());
+        let direction = self.frame.local_vector(ray.direction());
+
+        // x^2 + y^2 = k^2 (h - z)^2, where k is the slope of the side
+        let k_squared = (self.radius / self.height).powi(2);
+        let below_apex = self.height - origin.z();
+        let mut hits = solve_quadratic(
+            direction.x().powi(2) + direction.y().powi(2) - k_squared * direction.z().powi(2),
+            2.0 * (origin.x() * direction.x()
+                + origin.y() * direction.y()
+                + k_squared * below_apex * direction.z()),
+            origin.x().powi(2) + origin.y().powi(2) - k_squared * below_apex.powi(2),
+        )
+        .into_iter()
+        .filter(|t| (t_min..=t_max).contains(t))
+        .filter_map(|t| {
+            let point = origin + t * direction;
+            if !(0.0..=self.height).contains(&point.z()) {
+                return None;
+            }
+            let normal = Vec3::new(point.x(), point.y(), k_squared * (self.height - point.z()));
+            let normal = if normal.near_zero() {
+                Vec3::new(0.0, 0.0, 1.0)
+            } else {
+                normal.unit_vector()
+            };
+            Some(LocalHit {
+                t,
+                normal,
+                u: angle(&point),
+                v: point.z() / self.height,
+            })
+        })
+        .collect::<Vec<_>>();
+
+        if self.capped {
+            hits.extend(hit_cap(
+                &origin,
+                &direction,
+                0.0,
+                self.radius,
+                -1.0,
+                t_min,
+                t_max,
+            ));
+        }
+
+        nearest(hits).map(|hit| self.frame.hit_record(ray, hit, &self.material))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let base = self.frame.origin;
+        let apex = self.frame.world_point(&Vec3::new(0.0, 0.0, self.height));
+        let extent = self.frame.extent(self.radius);
+        Some(Aabb::from_points(&[base - extent, base + extent, apex]))
+    }
+}
+
+// A flat ring around `center` facing along `normal`. Its u coordinate runs around the center and
+// v from the inner to the outer edge.
+pub struct Annulus {
+    frame: Frame,
+    inner_radius: f64,
+    outer_radius: f64,
+    material: std::sync::Arc<dyn Material>,
+}
+
+impl Annulus {
+    pub fn new(
+        center: Vec3,
+        normal: Vec3,
+        inner_radius: f64,
+        outer_radius: f64,
+        material: std::sync::Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            frame: Frame::new(center, normal),
+            inner_radius,
+            outer_radius,
+            material,
+        }
+    }
+
+    fn area(&self) -> f64 {
+        std::f64::consts::PI * (self.outer_radius.powi(2) - self.inner_radius.powi(2))
+    }
+}
+
+impl Hittable for Annulus {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t, point) = hit_ring(
+            &self.frame.local_point(ray.origin()),
+            &self.frame.local_vector(ray.direction()),
+            0.0,
+            self.inner_radius,
+            self.outer_radius,
+            t_min,
+            t_max,
+        )?;
+
+        let distance = (point.x().powi(2) + point.y().powi(2)).sqrt();
+        let hit = LocalHit {
+            t,
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            u: angle(&point),
+            v: (distance - self.inner_radius) / (self.outer_radius - self.inner_radius),
+        };
+        Some(self.frame.hit_record(ray, hit, &self.material))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let center = self.frame.origin;
+        let extent = self.frame.extent(self.outer_radius);
+        Some(Aabb::from_points(&[center - extent, center + extent]))
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let hit_record = match self.hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::INFINITY) {
+            Some(hit_record) => hit_record,
+            None => return 0.0,
+        };
+
+        let distance_squared = hit_record.t().powi(2) * direction.length_squared();
+        let cosine = (direction.dot(&self.frame.w) / direction.length()).abs();
+        distance_squared / (cosine * self.area())
+    }
+
+    fn random(&self, origin: &Vec3, rng: &mut Pcg32) -> Vec3 {
+        // uniform in area, so the squared distance from the center is uniform
+        let inner_squared = self.inner_radius.powi(2);
+        let distance = rng
+            .random_f64(Some((inner_squared, self.outer_radius.powi(2))))
+            .sqrt();
+        let phi = 2.0 * std::f64::consts::PI * rng.random_f64(None);
+        let point =
+            self.frame
+                .world_point(&Vec3::new(distance * phi.cos(), distance * phi.sin(), 0.0));
+        point - origin
+    }
+}
+
+// A flat disk around `center` facing along `normal`, an annulus without a hole.
+pub struct Disk {
+    annulus: Annulus,
+}
+
+impl Disk {
+    pub fn new(
+        center: Vec3,
+        normal: Vec3,
+        radius: f64,
+        material: std::sync::Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            annulus: Annulus::new(center, normal, 0.0, radius, material),
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.annulus.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.annulus.bounding_box()
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.annulus.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3, rng: &mut Pcg32) -> Vec3 {
+        self.annulus.random(origin, rng)
+    }
+}
+
+// A torus around `center` whose tube of `minor_radius` circles `axis` at `major_radius`. Its u
+// coordinate runs around the axis and v around the tube.
+//
+// A spindle torus, whose `minor_radius` is at least its `major_radius`, also shows the surface
+// where its tube overlaps itself around the axis, so scenes only accept smaller minor radii.
+pub struct Torus {
+    frame: Frame,
+    major_radius: f64,
+    minor_radius: f64,
+    material: std::sync::Arc<dyn Material>,
+}
+
+impl Torus {
+    pub fn new(
+        center: Vec3,
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        material: std::sync::Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            frame: Frame::new(center, axis),
+            major_radius,
+            minor_radius,
+            material,
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let origin = self.frame.local_point(ray.origin());
+        let direction = self.frame.local_vector(ray.direction());
+        let length = direction.length();
+        let unit = direction / length;
+
+        // skip rays that miss the bounding sphere, and otherwise solve from a point near the torus
+        // since the quartic loses precision quickly with the distance to the origin
+        let bound = self.major_radius + self.minor_radius;
+        let closest = -origin.dot(&unit);
+        let start = origin + closest * unit;
+        if start.length_squared() > bound * bound {
+            return None;
+        }
+        let shift = closest - bound;
+        let start = origin + shift * unit;
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + y^2) along p = start + s * unit
+        let major_squared = self.major_radius.powi(2);
+        let g = start.length_squared();
+        let f = start.dot(&unit);
+        let e = g + major_squared - self.minor_radius.powi(2);
+        let roots = solve_quartic(
+            4.0 * f,
+            4.0 * f * f + 2.0 * e - 4.0 * major_squared * (1.0 - unit.z().powi(2)),
+            4.0 * f * e - 8.0 * major_squared * (f - start.z() * unit.z()),
+            e * e - 4.0 * major_squared * (g - start.z().powi(2)),
+        );
+
+        let t = roots
+            .into_iter()
+            .map(|s| (shift + s) / length)
+            .filter(|t| (t_min..=t_max).contains(t))
+            .min_by(|a, b| a.partial_cmp(b).unwrap())?;
+
+        let point = origin + t * direction;
+        let distance = (point.x().powi(2) + point.y().powi(2)).sqrt();
+        let core = Vec3::new(point.x(), point.y(), 0.0) * (self.major_radius / distance);
+        let hit = LocalHit {
+            t,
+            normal: (point - core) / self.minor_radius,
+            u: angle(&point),
+            v: (point.z().atan2(distance - self.major_radius) + std::f64::consts::PI)
+                / (2.0 * std::f64::consts::PI),
+        };
+        Some(self.frame.hit_record(ray, hit, &self.material))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let center = self.frame.origin;
+        let tube = Vec3::new(self.minor_radius, self.minor_radius, self.minor_radius);
+        let extent = self.frame.extent(self.major_radius) + tube;
+        Some(Aabb::from_points(&[center - extent, center + extent]))
+    }
+}
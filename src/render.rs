@@ -0,0 +1,484 @@
+use crate::buffer::Buffer;
+use crate::bvh::BvhNode;
+use crate::color::Color;
+use crate::compute::Compute;
+use crate::film::Film;
+use crate::hittable::{HitRecord, Hittable};
+use crate::hittable_list::HittableList;
+use crate::number::Pcg32;
+use crate::pdf::{HittablePdf, MixturePdf, Pdf};
+use crate::pixel::Pixel;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::tile::Tile;
+use crate::vec3::Vec3;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum RenderError {
+    FilmSize {
+        film: (usize, usize),
+        scene: (usize, usize),
+    },
+}
+
+impl Display for RenderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::FilmSize { film, scene } => write!(
+                f,
+                "film is {}x{} but the scene renders at {}x{}",
+                film.0, film.1, scene.0, scene.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+pub struct Renderer {
+    threads: usize,
+    tile_size: usize,
+    seed: u64,
+}
+
+impl Renderer {
+    // A render needs at least one thread and tiles of at least one pixel, so zero is raised to one.
+    pub fn new(threads: usize) -> Self {
+        Self {
+            threads: threads.max(1),
+            tile_size: 16,
+            seed: 0,
+        }
+    }
+
+    pub fn with_tile_size(mut self, tile_size: usize) -> Self {
+        self.tile_size = tile_size.max(1);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn tile_size(&self) -> usize {
+        self.tile_size
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn render(&self, scene: Scene) -> Buffer {
+        self.render_film(scene).to_buffer()
+    }
+
+    // Like `render`, but keeps the per-pixel sample sums and counts, for example to save a
+    // checkpoint or a heatmap of an adaptive render.
+    pub fn render_film(&self, scene: Scene) -> Film {
+        let settings = scene.settings();
+        let film = Film::new(settings.width(), settings.height());
+        let samples_per_pixel = settings.samples_per_pixel();
+        self.render_passes(scene, film, samples_per_pixel, |_| {})
+    }
+
+    // Renders in passes that each add up to `pass_samples` samples to every pixel, handing the
+    // film to `on_pass` after each pass so that a partial render can be saved.
+    pub fn render_progressive<F>(
+        &self,
+        scene: Scene,
+        pass_samples: usize,
+        on_pass: F,
+    ) -> Result<Film, RenderError>
+    where
+        F: FnMut(&Film),
+    {
+        let settings = scene.settings();
+        let film = Film::new(settings.width(), settings.height());
+        self.resume(scene, film, pass_samples, on_pass)
+    }
+
+    // Continues a progressive render from a film rendered earlier with the same scene and seed,
+    // adding samples until the scene's sample count is reached.
+    pub fn resume<F>(
+        &self,
+        scene: Scene,
+        film: Film,
+        pass_samples: usize,
+        on_pass: F,
+    ) -> Result<Film, RenderError>
+    where
+        F: FnMut(&Film),
+    {
+        let settings = scene.settings();
+        if (film.width(), film.height()) != (settings.width(), settings.height()) {
+            return Err(RenderError::FilmSize {
+                film: (film.width(), film.height()),
+                scene: (settings.width(), settings.height()),
+            });
+        }
+        Ok(self.render_passes(scene, film, pass_samples, on_pass))
+    }
+
+    fn render_passes<F>(
+        &self,
+        scene: Scene,
+        film: Film,
+        pass_samples: usize,
+        mut on_pass: F,
+    ) -> Film
+    where
+        F: FnMut(&Film),
+    {
+        let settings = scene.settings();
+
+        // image
+        let image_width = settings.width();
+        let image_height = settings.height();
+        let samples_per_pixel = settings.samples_per_pixel();
+        let min_samples_per_pixel = settings.min_samples_per_pixel().clamp(1, samples_per_pixel);
+        let sampling = Sampling {
+            samples_per_pixel,
+            min_samples_per_pixel,
+            noise_threshold: settings.noise_threshold(),
+        };
+        let max_depth = settings.max_depth();
+        let pass_samples = pass_samples.max(1);
+        let seed = self.seed;
+
+        // world
+        let world = std::sync::Arc::new(World {
+            objects: BvhNode::with_unbounded(scene.world()),
+            scene,
+        });
+
+        // processor
+        let mut functions = Vec::new();
+        for _ in 0..self.threads {
+            let world = world.clone();
+            functions.push(move |(tile, film): &(Tile, Film)| {
+                let mut film = film.clone();
+                for (local, pixel) in tile.pixels() {
+                    let index = (pixel.j() * image_width + pixel.i()) as u64;
+
+                    // samples are taken in batches of the minimum count until the pixel is
+                    // finished or reaches the sample count of this pass
+                    let mut samples = film.count(&local);
+                    let target = samples.saturating_add(pass_samples).min(samples_per_pixel);
+                    while samples < target && !sampling.finished(&film, &local) {
+                        let batch = (samples + min_samples_per_pixel).min(target);
+                        for sample in samples..batch {
+                            let mut rng = Pcg32::for_sample(seed, index, sample as u64);
+                            let u = (pixel.i() as f64 + rng.random_f64(None))
+                                / (image_width as f64 - 1.0);
+                            let v = (pixel.j() as f64 + rng.random_f64(None))
+                                / (image_height as f64 - 1.0);
+                            let ray = world.scene.camera().get_ray(u, v, &mut rng);
+                            let color = ray_color(&ray, &world, max_depth, &mut rng, None);
+                            film.add(&local, color);
+                        }
+                        samples = batch;
+                    }
+                }
+                film
+            });
+        }
+
+        let (compute, receiver) = Compute::new(functions);
+
+        // orchestrator
+        let tiles = Tile::split(image_width, image_height, self.tile_size);
+        let mut film = film;
+        while !sampling.all_finished(&film) {
+            let jobs = tiles
+                .iter()
+                .filter(|tile| {
+                    tile.pixels()
+                        .any(|(_, pixel)| !sampling.finished(&film, &pixel))
+                })
+                .map(|tile| (tile.clone(), film.tile(tile)))
+                .collect();
+
+            film = compute.compute_all(
+                &receiver,
+                jobs,
+                |mut film, (tile, _), rendered| {
+                    film.set_tile(tile, &rendered);
+                    film
+                },
+                film,
+            );
+            on_pass(&film);
+        }
+
+        film
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Sampling {
+    samples_per_pixel: usize,
+    min_samples_per_pixel: usize,
+    noise_threshold: Option<f64>,
+}
+
+impl Sampling {
+    fn finished(&self, film: &Film, pixel: &Pixel) -> bool {
+        let count = film.count(pixel);
+        count >= self.samples_per_pixel
+            || (count >= self.min_samples_per_pixel
+                && matches!(self.noise_threshold, Some(threshold) if film.relative_error(pixel) < threshold))
+    }
+
+    fn all_finished(&self, film: &Film) -> bool {
+        (0..film.height())
+            .flat_map(|j| (0..film.width()).map(move |i| Pixel::new(i, j)))
+            .all(|pixel| self.finished(film, &pixel))
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new(num_cpus::get())
+    }
+}
+
+// Everything rays are traced through, shared by the workers: the scene, with its objects in a BVH.
+struct World {
+    objects: HittableList,
+    scene: Scene,
+}
+
+impl World {
+    // The first point before `t_max` where the ray scatters inside a medium, if any.
+    fn collide(&self, ray: &Ray, t_min: f64, t_max: f64, rng: &mut Pcg32) -> Option<HitRecord> {
+        let mut closest_so_far = t_max;
+        let mut closest_hit_record = None;
+
+        for medium in self.scene.media() {
+            if let Some(hit_record) = medium.sample(ray, t_min, closest_so_far, rng) {
+                closest_so_far = hit_record.t();
+                closest_hit_record = Some(hit_record);
+            }
+        }
+
+        closest_hit_record
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64, rng: &mut Pcg32) -> f64 {
+        let mut transmittance = 1.0;
+        for medium in self.scene.media() {
+            transmittance *= medium.transmittance(ray, t_min, t_max, rng);
+            if transmittance <= 0.0 {
+                return 0.0;
+            }
+        }
+        transmittance
+    }
+}
+
+fn ray_color(
+    ray: &Ray,
+    world: &World,
+    depth: usize,
+    rng: &mut Pcg32,
+    scattering_pdf: Option<f64>,
+) -> Color {
+    if depth == 0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    // a ray scattering inside a medium never reaches the surface behind it
+    let surface = world.objects.hit(ray, 0.001, f64::INFINITY);
+    let t_max = surface
+        .as_ref()
+        .map_or(f64::INFINITY, |hit_record| hit_record.t());
+    let hit_record = match world.collide(ray, 0.001, t_max, rng).or(surface) {
+        Some(hit_record) => hit_record,
+        None => return world.scene.background().color(ray),
+    };
+
+    // emission found by a scattered ray, weighted against light sampling
+    let material = hit_record.material();
+    let mut color = material.emitted(&hit_record);
+    if let Some(scattering_pdf) = scattering_pdf {
+        let light_pdf = light_pdf(world.scene.lights(), *ray.origin()).value(ray.direction());
+        color = power_heuristic(scattering_pdf, light_pdf) * color;
+    }
+
+    let (attenuation, scattered) = match material.scatter(ray, &hit_record, rng) {
+        Some(scatter) => scatter,
+        None => return color,
+    };
+
+    let scattering_pdf = material.scattering_pdf(ray, &hit_record, &scattered);
+    if scattering_pdf.is_some() {
+        color = color + next_event(ray, &hit_record, &attenuation, world, rng);
+    }
+
+    color + attenuation * ray_color(&scattered, world, depth - 1, rng, scattering_pdf)
+}
+
+// Samples a direction towards the lights and returns the light arriving along it, weighted
+// against the material having scattered in the same direction. Media along the way let through
+// only part of the light.
+fn next_event(
+    ray: &Ray,
+    hit_record: &HitRecord,
+    attenuation: &Color,
+    world: &World,
+    rng: &mut Pcg32,
+) -> Color {
+    let light_pdf = light_pdf(world.scene.lights(), *hit_record.point());
+    if light_pdf.is_empty() {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let shadow = Ray::new(*hit_record.point(), light_pdf.generate(rng), ray.time());
+    let light_pdf_value = light_pdf.value(shadow.direction());
+    let scattering_pdf = hit_record
+        .material()
+        .scattering_pdf(ray, hit_record, &shadow)
+        .unwrap_or(0.0);
+    if light_pdf_value <= 0.0 || scattering_pdf <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    match world.objects.hit(&shadow, 0.001, f64::INFINITY) {
+        Some(light) => {
+            let weight = power_heuristic(light_pdf_value, scattering_pdf);
+            let transmittance = world.transmittance(&shadow, 0.001, light.t(), rng);
+            (weight * transmittance * scattering_pdf / light_pdf_value)
+                * attenuation.clone()
+                * light.material().emitted(&light)
+        }
+        None => Color::new(0.0, 0.0, 0.0),
+    }
+}
+
+fn light_pdf(lights: &HittableList, origin: Vec3) -> MixturePdf<HittablePdf<'_>> {
+    MixturePdf::new(
+        lights
+            .objects()
+            .iter()
+            .map(|light| HittablePdf::new(light.as_ref(), origin))
+            .collect(),
+    )
+}
+
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let pdf = pdf.powi(2);
+    pdf / (pdf + other_pdf.powi(2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::background::Background;
+    use crate::camera::Camera;
+    use crate::checkpoint::{read_checkpoint, write_checkpoint};
+    use crate::hittable::Sphere;
+    use crate::material::{Isotropic, Lambertian};
+    use crate::medium::ConstantMedium;
+    use crate::scene::RenderSettings;
+
+    fn small_scene(settings: RenderSettings) -> Scene {
+        let mut world = HittableList::new();
+        world.add(std::sync::Arc::new(Sphere::new(
+            Vec3::new(0.0, 0.0, -1.0),
+            0.5,
+            std::sync::Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )));
+        let fog = Sphere::new(
+            Vec3::new(0.4, 0.2, -0.5),
+            0.3,
+            std::sync::Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        );
+        world.add(std::sync::Arc::new(ConstantMedium::new(
+            std::sync::Arc::new(fog),
+            2.0,
+            std::sync::Arc::new(Isotropic::new(Color::new(0.8, 0.8, 0.8))),
+        )));
+        let camera = Camera::new(
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            45.0,
+            settings.aspect_ratio(),
+            0.0,
+            2.0,
+        );
+        Scene::new(settings, camera, world, Background::default())
+    }
+
+    fn assert_same(film: &Film, other: &Film) {
+        assert_eq!(
+            (film.width(), film.height()),
+            (other.width(), other.height())
+        );
+        for j in 0..film.height() {
+            for i in 0..film.width() {
+                let pixel = Pixel::new(i, j);
+                let (sum, other_sum) = (film.sum(&pixel), other.sum(&pixel));
+                assert_eq!(film.count(&pixel), other.count(&pixel));
+                assert_eq!(
+                    (sum.r(), sum.g(), sum.b()),
+                    (other_sum.r(), other_sum.g(), other_sum.b())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn zero_threads_tiles_and_samples_are_raised_to_one() {
+        let renderer = Renderer::new(0).with_tile_size(0);
+        assert_eq!((renderer.threads(), renderer.tile_size()), (1, 1));
+
+        let settings = RenderSettings::new(4, 3, 0, 4);
+        assert_eq!(settings.samples_per_pixel(), 1);
+
+        let film = renderer.render_film(small_scene(settings));
+        assert!((0..3).all(|j| (0..4).all(|i| film.count(&Pixel::new(i, j)) == 1)));
+    }
+
+    #[test]
+    fn resumed_renders_equal_direct_renders() {
+        let renderer = Renderer::new(2).with_tile_size(3).with_seed(5);
+        let direct = renderer.render_film(small_scene(RenderSettings::new(6, 4, 8, 4)));
+
+        let partial = renderer.render_film(small_scene(RenderSettings::new(6, 4, 3, 4)));
+        let mut checkpoint = Vec::new();
+        write_checkpoint(&mut checkpoint, 5, 0, &partial).unwrap();
+        let (_, _, film) = read_checkpoint(&checkpoint[..]).unwrap();
+        let resumed = renderer
+            .resume(
+                small_scene(RenderSettings::new(6, 4, 8, 4)),
+                film,
+                2,
+                |_| {},
+            )
+            .unwrap();
+
+        assert_same(&resumed, &direct);
+    }
+
+    #[test]
+    fn renders_do_not_depend_on_threads_or_tiles() {
+        let render = |threads, tile_size| {
+            Renderer::new(threads)
+                .with_tile_size(tile_size)
+                .with_seed(3)
+                .render_film(small_scene(RenderSettings::new(7, 5, 4, 6)))
+        };
+
+        let film = render(1, 16);
+        for &(threads, tile_size) in &[(1, 1), (3, 2), (4, 3), (8, 5)] {
+            assert_same(&render(threads, tile_size), &film);
+        }
+    }
+}
@@ -0,0 +1,1415 @@
+use crate::background::Background;
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::grid::VoxelGrid;
+use crate::hittable::{
+    Axis, Cuboid, Hittable, Moving, MovingSphere, Plane, Quad, Rectangle, Sphere, Transformed,
+    Triangle,
+};
+use crate::hittable_list::HittableList;
+use crate::json::{JsonError, JsonKind, JsonValue};
+use crate::material::{
+    Dielectric, DiffuseLight, HenyeyGreenstein, Isotropic, Lambertian, Material, Metal,
+};
+use crate::matrix::{Matrix4, Transform};
+use crate::medium::{ConstantMedium, GridMedium};
+use crate::number::Pcg32;
+use crate::obj::ObjError;
+use crate::quadric::{Annulus, Cone, Cylinder, Disk, Torus};
+use crate::texture::{
+    CheckerTexture, ImageTexture, MarbleTexture, NoiseTexture, SolidColor, Texture, WoodTexture,
+    WrapMode,
+};
+use crate::vec3::Vec3;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+pub struct RenderSettings {
+    width: usize,
+    height: usize,
+    samples_per_pixel: usize,
+    max_depth: usize,
+    min_samples_per_pixel: usize,
+    noise_threshold: Option<f64>,
+}
+
+impl RenderSettings {
+    // Rays span from the first to the last pixel of a row or column, so images are at least 2
+    // pixels wide and high, and every pixel takes at least one sample. Smaller values are raised.
+    pub fn new(width: usize, height: usize, samples_per_pixel: usize, max_depth: usize) -> Self {
+        Self {
+            width: width.max(2),
+            height: height.max(2),
+            samples_per_pixel: samples_per_pixel.max(1),
+            max_depth,
+            min_samples_per_pixel: 16,
+            noise_threshold: None,
+        }
+    }
+
+    pub fn with_adaptive_sampling(
+        mut self,
+        min_samples_per_pixel: usize,
+        noise_threshold: Option<f64>,
+    ) -> Self {
+        self.min_samples_per_pixel = min_samples_per_pixel;
+        self.noise_threshold = noise_threshold;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn min_samples_per_pixel(&self) -> usize {
+        self.min_samples_per_pixel
+    }
+
+    pub fn noise_threshold(&self) -> Option<f64> {
+        self.noise_threshold
+    }
+
+    pub fn aspect_ratio(&self) -> f64 {
+        self.width as f64 / self.height as f64
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self::new(1920, 1080, 500, 50)
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(PathBuf, std::io::Error),
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    Obj(ObjError),
+}
+
+impl Display for SceneError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            SceneError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+            SceneError::Obj(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+pub struct Scene {
+    settings: RenderSettings,
+    camera: Camera,
+    world: HittableList,
+    lights: HittableList,
+    media: Vec<GridMedium>,
+    background: Background,
+    source_hash: u64,
+}
+
+impl Scene {
+    pub fn new(
+        settings: RenderSettings,
+        camera: Camera,
+        world: HittableList,
+        background: Background,
+    ) -> Self {
+        Self {
+            settings,
+            camera,
+            world,
+            lights: HittableList::new(),
+            media: Vec::new(),
+            background,
+            source_hash: 0,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SceneError> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|error| SceneError::Io(path.to_path_buf(), error))?;
+        Self::parse(&source, path)
+    }
+
+    // Builds a scene from the source of the file at `path`, which locates the files it refers to.
+    fn parse(source: &str, path: &Path) -> Result<Self, SceneError> {
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+        let parse_error = |error: JsonError| SceneError::Parse {
+            path: path.to_path_buf(),
+            line: error.line(),
+            message: error.message().to_string(),
+        };
+
+        let root = crate::json::parse(source).map_err(parse_error)?;
+        let scene = SceneBuilder {
+            directory,
+            meshes: std::cell::RefCell::new(HashMap::new()),
+        }
+        .scene(&root)
+        .map_err(|error| match error {
+            BuildError::Json(error) => parse_error(error),
+            BuildError::Obj(error) => SceneError::Obj(error),
+            BuildError::Io(path, error) => SceneError::Io(path, error),
+        })?;
+
+        let bytes: Vec<u64> = source.bytes().map(u64::from).collect();
+        Ok(Self {
+            source_hash: Pcg32::hashed(&bytes).next_u64(),
+            ..scene
+        })
+    }
+
+    pub fn random(rng: &mut Pcg32) -> Self {
+        let settings = RenderSettings::default();
+        let camera = Camera::new(
+            Vec3::new(13.0, 2.0, 3.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            20.0,
+            settings.aspect_ratio(),
+            0.1,
+            10.0,
+        );
+        Self::new(settings, camera, random_scene(rng), Background::default())
+    }
+
+    pub fn with_lights(mut self, lights: HittableList) -> Self {
+        self.lights = lights;
+        self
+    }
+
+    pub fn with_media(mut self, media: Vec<GridMedium>) -> Self {
+        self.media = media;
+        self
+    }
+
+    pub fn with_settings(mut self, settings: RenderSettings) -> Self {
+        self.camera = self.camera.with_aspect_ratio(settings.aspect_ratio());
+        self.settings = settings;
+        self
+    }
+
+    pub fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    pub fn lights(&self) -> &HittableList {
+        &self.lights
+    }
+
+    pub fn media(&self) -> &[GridMedium] {
+        &self.media
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    pub fn world(&self) -> &HittableList {
+        &self.world
+    }
+
+    pub fn background(&self) -> &Background {
+        &self.background
+    }
+
+    // A hash of what a render's samples depend on besides the seed: the scene file, if the scene
+    // was loaded, the image size and the maximum depth. It does not cover files the scene refers
+    // to, such as meshes and images.
+    pub fn fingerprint(&self) -> u64 {
+        Pcg32::hashed(&[
+            self.source_hash,
+            self.settings.width() as u64,
+            self.settings.height() as u64,
+            self.settings.max_depth() as u64,
+        ])
+        .next_u64()
+    }
+}
+
+fn random_scene(rng: &mut Pcg32) -> HittableList {
+    let mut world = HittableList::new();
+
+    let ground_material = std::sync::Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    world.add(std::sync::Arc::new(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        ground_material,
+    )));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_material = rng.random_f64(None);
+            let center = Vec3::new(
+                a as f64 + 0.9 * rng.random_f64(None),
+                0.2,
+                b as f64 + 0.9 * rng.random_f64(None),
+            );
+
+            if (center - Vec3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                if choose_material < 0.8 {
+                    let albedo = Color::random(None, rng) * Color::random(None, rng);
+                    let material = std::sync::Arc::new(Lambertian::new(albedo));
+                    world.add(std::sync::Arc::new(Sphere::new(center, 0.2, material)));
+                } else if choose_material < 0.95 {
+                    let albedo = Color::random(Some((0.5, 1.0)), rng);
+                    let fuzz = rng.random_f64(Some((0.0, 0.5)));
+                    let material = std::sync::Arc::new(Metal::new(albedo, fuzz));
+                    world.add(std::sync::Arc::new(Sphere::new(center, 0.2, material)));
+                } else {
+                    let material = std::sync::Arc::new(Dielectric::new(1.5));
+                    world.add(std::sync::Arc::new(Sphere::new(center, 0.2, material)));
+                }
+            }
+        }
+    }
+
+    let material_1 = std::sync::Arc::new(Dielectric::new(1.5));
+    world.add(std::sync::Arc::new(Sphere::new(
+        Vec3::new(0.0, 1.0, 0.0),
+        1.0,
+        material_1,
+    )));
+
+    let material_2 = std::sync::Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
+    world.add(std::sync::Arc::new(Sphere::new(
+        Vec3::new(-4.0, 1.0, 0.0),
+        1.0,
+        material_2,
+    )));
+
+    let material_3 = std::sync::Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
+    world.add(std::sync::Arc::new(Sphere::new(
+        Vec3::new(4.0, 1.0, 0.0),
+        1.0,
+        material_3,
+    )));
+
+    world
+}
+
+enum BuildError {
+    Json(JsonError),
+    Obj(ObjError),
+    Io(PathBuf, std::io::Error),
+}
+
+impl From<JsonError> for BuildError {
+    fn from(error: JsonError) -> Self {
+        BuildError::Json(error)
+    }
+}
+
+type MeshCache = HashMap<(PathBuf, Option<String>), std::sync::Arc<dyn Hittable>>;
+
+struct SceneBuilder<'a> {
+    directory: &'a Path,
+    meshes: std::cell::RefCell<MeshCache>,
+}
+
+impl<'a> SceneBuilder<'a> {
+    fn scene(&self, root: &JsonValue) -> Result<Scene, BuildError> {
+        check_keys(
+            root,
+            &[
+                "render",
+                "camera",
+                "background",
+                "textures",
+                "materials",
+                "objects",
+                "media",
+            ],
+        )?;
+
+        let settings = match root.get("render") {
+            Some(render) => self.settings(render)?,
+            None => RenderSettings::default(),
+        };
+        let camera = self.camera(field(root, "camera")?, settings.aspect_ratio())?;
+
+        let mut textures = HashMap::new();
+        if let Some(descriptions) = root.get("textures") {
+            for (name, description) in object(descriptions)? {
+                let texture = self.texture(description, &textures)?;
+                textures.insert(name.clone(), texture);
+            }
+        }
+
+        let mut materials = HashMap::new();
+        let mut emissive = HashSet::new();
+        if let Some(descriptions) = root.get("materials") {
+            for (name, description) in object(descriptions)? {
+                materials.insert(name.clone(), self.material(description, &textures)?);
+                if string(field(description, "type")?)? == "diffuse_light" {
+                    emissive.insert(name.as_str());
+                }
+            }
+        }
+
+        let objects = field(root, "objects")?;
+        let mut world = HittableList::new();
+        let mut lights = HittableList::new();
+        for description in array(objects)? {
+            let object = self.object(description, &materials)?;
+            if is_light(description, &emissive)? {
+                lights.add(object.clone());
+            }
+            world.add(object);
+        }
+
+        let mut media = Vec::new();
+        if let Some(descriptions) = root.get("media") {
+            for description in array(descriptions)? {
+                media.push(self.medium(description, &materials)?);
+            }
+        }
+
+        if world.objects().is_empty() && media.is_empty() {
+            return Err(
+                JsonError::new(objects.line(), String::from("scene has no objects")).into(),
+            );
+        }
+
+        let background = match root.get("background") {
+            Some(background) => self.background(background)?,
+            None => Background::default(),
+        };
+
+        Ok(Scene::new(settings, camera, world, background)
+            .with_lights(lights)
+            .with_media(media))
+    }
+
+    fn medium(
+        &self,
+        description: &JsonValue,
+        materials: &HashMap<String, std::sync::Arc<dyn Material>>,
+    ) -> Result<GridMedium, BuildError> {
+        let kind = string(field(description, "type")?)?;
+        if kind != "grid" {
+            return Err(JsonError::new(
+                field(description, "type")?.line(),
+                format!("unknown medium type '{}'", kind),
+            )
+            .into());
+        }
+        check_keys(
+            description,
+            &[
+                "type", "path", "corner_0", "corner_1", "density", "material",
+            ],
+        )?;
+
+        let path = self.directory.join(string(field(description, "path")?)?);
+        let grid = VoxelGrid::load(&path).map_err(|error| BuildError::Io(path, error))?;
+
+        let value = field(description, "material")?;
+        let name = string(value)?;
+        let phase_function = materials
+            .get(name)
+            .cloned()
+            .ok_or_else(|| JsonError::new(value.line(), format!("unknown material '{}'", name)))?;
+
+        let corner_0 = vec3(field(description, "corner_0")?)?;
+        let corner_1 = vec3(field(description, "corner_1")?)?;
+        if (0..3).any(|k| corner_0[k] == corner_1[k]) {
+            return Err(JsonError::new(
+                description.line(),
+                String::from("medium corners must differ in every coordinate"),
+            )
+            .into());
+        }
+
+        Ok(GridMedium::new(
+            std::sync::Arc::new(grid),
+            corner_0,
+            corner_1,
+            optional(description, "density", positive_number)?.unwrap_or(1.0),
+            phase_function,
+        ))
+    }
+
+    fn background(&self, background: &JsonValue) -> Result<Background, BuildError> {
+        if let JsonKind::Array(_) = background.kind() {
+            return Ok(Background::Solid(color(background)?));
+        }
+
+        let kind = string(field(background, "type")?)?;
+        Ok(match kind {
+            "solid" => {
+                check_keys(background, &["type", "color"])?;
+                Background::Solid(color(field(background, "color")?)?)
+            }
+            "gradient" => {
+                check_keys(background, &["type", "bottom", "top"])?;
+                Background::Gradient {
+                    bottom: color(field(background, "bottom")?)?,
+                    top: color(field(background, "top")?)?,
+                }
+            }
+            _ => {
+                return Err(JsonError::new(
+                    field(background, "type")?.line(),
+                    format!("unknown background type '{}'", kind),
+                )
+                .into())
+            }
+        })
+    }
+
+    fn settings(&self, render: &JsonValue) -> Result<RenderSettings, BuildError> {
+        check_keys(
+            render,
+            &[
+                "width",
+                "height",
+                "samples_per_pixel",
+                "max_depth",
+                "min_samples_per_pixel",
+                "noise_threshold",
+            ],
+        )?;
+        let default = RenderSettings::default();
+
+        Ok(RenderSettings::new(
+            optional(render, "width", image_size)?.unwrap_or(default.width),
+            optional(render, "height", image_size)?.unwrap_or(default.height),
+            optional(render, "samples_per_pixel", positive_usize)?
+                .unwrap_or(default.samples_per_pixel),
+            optional(render, "max_depth", positive_usize)?.unwrap_or(default.max_depth),
+        )
+        .with_adaptive_sampling(
+            optional(render, "min_samples_per_pixel", positive_usize)?
+                .unwrap_or(default.min_samples_per_pixel),
+            optional(render, "noise_threshold", positive_number)?,
+        ))
+    }
+
+    fn camera(&self, camera: &JsonValue, aspect_ratio: f64) -> Result<Camera, BuildError> {
+        check_keys(
+            camera,
+            &[
+                "look_from",
+                "look_at",
+                "vertical_up",
+                "vertical_field_of_view",
+                "aperture",
+                "focus_distance",
+                "shutter",
+            ],
+        )?;
+
+        let look_from = vec3(field(camera, "look_from")?)?;
+        let look_at = vec3(field(camera, "look_at")?)?;
+        let shutter = optional(camera, "shutter", numbers)?;
+
+        let camera = Camera::new(
+            look_from,
+            look_at,
+            optional(camera, "vertical_up", vec3)?.unwrap_or_else(|| Vec3::new(0.0, 1.0, 0.0)),
+            optional(camera, "vertical_field_of_view", number)?.unwrap_or(90.0),
+            aspect_ratio,
+            optional(camera, "aperture", number)?.unwrap_or(0.0),
+            optional(camera, "focus_distance", number)?
+                .unwrap_or_else(|| (look_from - look_at).length()),
+        );
+
+        Ok(match shutter {
+            Some([shutter_open, shutter_close]) => camera.with_shutter(shutter_open, shutter_close),
+            None => camera,
+        })
+    }
+
+    fn texture(
+        &self,
+        description: &JsonValue,
+        textures: &HashMap<String, std::sync::Arc<dyn Texture>>,
+    ) -> Result<std::sync::Arc<dyn Texture>, BuildError> {
+        let kind = string(field(description, "type")?)?;
+
+        Ok(match kind {
+            "solid" => {
+                check_keys(description, &["type", "color"])?;
+                std::sync::Arc::new(SolidColor::new(color(field(description, "color")?)?))
+            }
+            "checker" => {
+                check_keys(description, &["type", "scale", "even", "odd"])?;
+                std::sync::Arc::new(CheckerTexture::new(
+                    optional(description, "scale", number)?.unwrap_or(1.0),
+                    texture(field(description, "even")?, textures)?,
+                    texture(field(description, "odd")?, textures)?,
+                ))
+            }
+            "noise" => {
+                check_keys(description, &["type", "seed", "scale", "octaves"])?;
+                std::sync::Arc::new(NoiseTexture::new(
+                    optional(description, "seed", seed)?.unwrap_or(0),
+                    optional(description, "scale", number)?.unwrap_or(1.0),
+                    optional(description, "octaves", positive_usize)?.unwrap_or(1),
+                ))
+            }
+            "marble" => {
+                check_keys(description, &["type", "seed", "scale", "color"])?;
+                std::sync::Arc::new(MarbleTexture::new(
+                    optional(description, "seed", seed)?.unwrap_or(0),
+                    optional(description, "scale", number)?.unwrap_or(1.0),
+                    optional(description, "color", color)?
+                        .unwrap_or_else(|| Color::new(1.0, 1.0, 1.0)),
+                ))
+            }
+            "wood" => {
+                check_keys(description, &["type", "seed", "scale", "light", "dark"])?;
+                std::sync::Arc::new(WoodTexture::new(
+                    optional(description, "seed", seed)?.unwrap_or(0),
+                    optional(description, "scale", number)?.unwrap_or(1.0),
+                    optional(description, "light", color)?
+                        .unwrap_or_else(|| Color::new(0.8, 0.6, 0.4)),
+                    optional(description, "dark", color)?
+                        .unwrap_or_else(|| Color::new(0.4, 0.25, 0.1)),
+                ))
+            }
+            "image" => {
+                check_keys(description, &["type", "path", "wrap"])?;
+                let path = field(description, "path")?;
+                let wrap_mode = match description.get("wrap") {
+                    Some(wrap) => WrapMode::from_name(string(wrap)?).ok_or_else(|| {
+                        JsonError::new(
+                            wrap.line(),
+                            format!("unknown wrap mode '{}'", string(wrap).unwrap()),
+                        )
+                    })?,
+                    None => WrapMode::Repeat,
+                };
+                let texture = ImageTexture::load(&self.directory.join(string(path)?), wrap_mode)
+                    .map_err(|error| {
+                        JsonError::new(
+                            path.line(),
+                            format!(
+                                "failed to load image '{}': {}",
+                                string(path).unwrap(),
+                                error
+                            ),
+                        )
+                    })?;
+                std::sync::Arc::new(texture)
+            }
+            _ => {
+                return Err(JsonError::new(
+                    field(description, "type")?.line(),
+                    format!("unknown texture type '{}'", kind),
+                )
+                .into())
+            }
+        })
+    }
+
+    fn material(
+        &self,
+        description: &JsonValue,
+        textures: &HashMap<String, std::sync::Arc<dyn Texture>>,
+    ) -> Result<std::sync::Arc<dyn Material>, BuildError> {
+        let kind = string(field(description, "type")?)?;
+
+        Ok(match kind {
+            "lambertian" => {
+                check_keys(description, &["type", "albedo"])?;
+                std::sync::Arc::new(Lambertian::textured(texture(
+                    field(description, "albedo")?,
+                    textures,
+                )?))
+            }
+            "metal" => {
+                check_keys(description, &["type", "albedo", "fuzz"])?;
+                std::sync::Arc::new(Metal::textured(
+                    texture(field(description, "albedo")?, textures)?,
+                    optional(description, "fuzz", number)?.unwrap_or(0.0),
+                ))
+            }
+            "dielectric" => {
+                check_keys(description, &["type", "index_of_refraction"])?;
+                std::sync::Arc::new(Dielectric::new(number(field(
+                    description,
+                    "index_of_refraction",
+                )?)?))
+            }
+            "diffuse_light" => {
+                check_keys(description, &["type", "emit"])?;
+                std::sync::Arc::new(DiffuseLight::textured(texture(
+                    field(description, "emit")?,
+                    textures,
+                )?))
+            }
+            "isotropic" => {
+                check_keys(description, &["type", "albedo"])?;
+                std::sync::Arc::new(Isotropic::textured(texture(
+                    field(description, "albedo")?,
+                    textures,
+                )?))
+            }
+            "henyey_greenstein" => {
+                check_keys(description, &["type", "albedo", "anisotropy"])?;
+                let anisotropy = optional(description, "anisotropy", number)?.unwrap_or(0.0);
+                if anisotropy.abs() >= 1.0 {
+                    return Err(JsonError::new(
+                        field(description, "anisotropy")?.line(),
+                        format!(
+                            "expected an anisotropy between -1 and 1, found {}",
+                            anisotropy
+                        ),
+                    )
+                    .into());
+                }
+                std::sync::Arc::new(HenyeyGreenstein::textured(
+                    texture(field(description, "albedo")?, textures)?,
+                    anisotropy,
+                ))
+            }
+            _ => {
+                return Err(JsonError::new(
+                    field(description, "type")?.line(),
+                    format!("unknown material type '{}'", kind),
+                )
+                .into())
+            }
+        })
+    }
+
+    fn object(
+        &self,
+        description: &JsonValue,
+        materials: &HashMap<String, std::sync::Arc<dyn Material>>,
+    ) -> Result<std::sync::Arc<dyn Hittable>, BuildError> {
+        let kind = string(field(description, "type")?)?;
+
+        let material = |key: &str| -> Result<std::sync::Arc<dyn Material>, JsonError> {
+            let value = field(description, key)?;
+            let name = string(value)?;
+            materials
+                .get(name)
+                .cloned()
+                .ok_or_else(|| JsonError::new(value.line(), format!("unknown material '{}'", name)))
+        };
+
+        let object: std::sync::Arc<dyn Hittable> = match kind {
+            "sphere" => {
+                check_object_keys(description, &["center", "radius", "material"])?;
+                std::sync::Arc::new(Sphere::new(
+                    vec3(field(description, "center")?)?,
+                    number(field(description, "radius")?)?,
+                    material("material")?,
+                ))
+            }
+            "moving_sphere" => {
+                check_object_keys(
+                    description,
+                    &[
+                        "center_0", "center_1", "time_0", "time_1", "radius", "material",
+                    ],
+                )?;
+                let (time_0, time_1) = time_interval(description)?;
+                std::sync::Arc::new(MovingSphere::new(
+                    vec3(field(description, "center_0")?)?,
+                    vec3(field(description, "center_1")?)?,
+                    time_0,
+                    time_1,
+                    number(field(description, "radius")?)?,
+                    material("material")?,
+                ))
+            }
+            "triangle" => {
+                check_object_keys(description, &["vertices", "normals", "uvs", "material"])?;
+                let [v0, v1, v2] = triple(field(description, "vertices")?, vec3)?;
+                let mut triangle = Triangle::new(v0, v1, v2, material("material")?);
+                if let Some(normals) = optional(description, "normals", |v| triple(v, vec3))? {
+                    triangle = triangle.with_normals(normals);
+                }
+                if let Some(uvs) = optional(description, "uvs", |v| triple(v, uv))? {
+                    triangle = triangle.with_uvs(uvs);
+                }
+                std::sync::Arc::new(triangle)
+            }
+            "quad" => {
+                check_object_keys(description, &["corner", "u", "v", "material"])?;
+                let u = vec3(field(description, "u")?)?;
+                let v = vec3(field(description, "v")?)?;
+                if u.cross(&v).near_zero() {
+                    return Err(JsonError::new(
+                        description.line(),
+                        String::from("quad edges u and v must be nonzero and not parallel"),
+                    )
+                    .into());
+                }
+                std::sync::Arc::new(Quad::new(
+                    vec3(field(description, "corner")?)?,
+                    u,
+                    v,
+                    material("material")?,
+                ))
+            }
+            "plane" => {
+                check_object_keys(description, &["point", "normal", "material"])?;
+                let normal = vec3(field(description, "normal")?)?;
+                if normal.near_zero() {
+                    return Err(JsonError::new(
+                        description.line(),
+                        String::from("plane normal must be nonzero"),
+                    )
+                    .into());
+                }
+                std::sync::Arc::new(Plane::new(
+                    vec3(field(description, "point")?)?,
+                    normal,
+                    material("material")?,
+                ))
+            }
+            "rectangle" => {
+                check_object_keys(description, &["corner_0", "corner_1", "material"])?;
+                let corner_0 = vec3(field(description, "corner_0")?)?;
+                let corner_1 = vec3(field(description, "corner_1")?)?;
+                let axis = [Axis::X, Axis::Y, Axis::Z]
+                    .iter()
+                    .filter(|axis| corner_0[axis.index()] == corner_1[axis.index()])
+                    .copied()
+                    .collect::<Vec<_>>();
+                let axis = match axis[..] {
+                    [axis] => axis,
+                    _ => {
+                        return Err(JsonError::new(
+                            description.line(),
+                            String::from(
+                                "rectangle corners must differ in exactly two coordinates",
+                            ),
+                        )
+                        .into())
+                    }
+                };
+                let (a, b) = axis.others();
+                std::sync::Arc::new(Rectangle::new(
+                    axis,
+                    (corner_0[a.index()], corner_1[a.index()]),
+                    (corner_0[b.index()], corner_1[b.index()]),
+                    corner_0[axis.index()],
+                    material("material")?,
+                ))
+            }
+            "box" => {
+                check_object_keys(description, &["corner_0", "corner_1", "material"])?;
+                std::sync::Arc::new(Cuboid::new(
+                    vec3(field(description, "corner_0")?)?,
+                    vec3(field(description, "corner_1")?)?,
+                    material("material")?,
+                ))
+            }
+            "cylinder" => {
+                check_object_keys(
+                    description,
+                    &["base", "top", "radius", "capped", "material"],
+                )?;
+                let base = vec3(field(description, "base")?)?;
+                let top = vec3(field(description, "top")?)?;
+                if (top - base).near_zero() {
+                    return Err(JsonError::new(
+                        description.line(),
+                        String::from("cylinder top must differ from its base"),
+                    )
+                    .into());
+                }
+                let cylinder = Cylinder::new(
+                    base,
+                    top,
+                    positive_number(field(description, "radius")?)?,
+                    material("material")?,
+                );
+                match optional(description, "capped", boolean)? {
+                    Some(true) => std::sync::Arc::new(cylinder.with_caps()),
+                    _ => std::sync::Arc::new(cylinder),
+                }
+            }
+            "cone" => {
+                check_object_keys(
+                    description,
+                    &["base", "apex", "radius", "capped", "material"],
+                )?;
+                let base = vec3(field(description, "base")?)?;
+                let apex = vec3(field(description, "apex")?)?;
+                if (apex - base).near_zero() {
+                    return Err(JsonError::new(
+                        description.line(),
+                        String::from("cone apex must differ from its base"),
+                    )
+                    .into());
+                }
+                let cone = Cone::new(
+                    base,
+                    apex,
+                    positive_number(field(description, "radius")?)?,
+                    material("material")?,
+                );
+                match optional(description, "capped", boolean)? {
+                    Some(true) => std::sync::Arc::new(cone.with_caps()),
+                    _ => std::sync::Arc::new(cone),
+                }
+            }
+            "disk" => {
+                check_object_keys(description, &["center", "normal", "radius", "material"])?;
+                std::sync::Arc::new(Disk::new(
+                    vec3(field(description, "center")?)?,
+                    direction(field(description, "normal")?)?,
+                    positive_number(field(description, "radius")?)?,
+                    material("material")?,
+                ))
+            }
+            "annulus" => {
+                check_object_keys(
+                    description,
+                    &[
+                        "center",
+                        "normal",
+                        "inner_radius",
+                        "outer_radius",
+                        "material",
+                    ],
+                )?;
+                let inner_radius = number(field(description, "inner_radius")?)?;
+                let outer_radius = positive_number(field(description, "outer_radius")?)?;
+                if !(0.0..outer_radius).contains(&inner_radius) {
+                    return Err(JsonError::new(
+                        description.line(),
+                        String::from(
+                            "annulus inner radius must be at least 0 and below the outer radius",
+                        ),
+                    )
+                    .into());
+                }
+                std::sync::Arc::new(Annulus::new(
+                    vec3(field(description, "center")?)?,
+                    direction(field(description, "normal")?)?,
+                    inner_radius,
+                    outer_radius,
+                    material("material")?,
+                ))
+            }
+            "torus" => {
+                check_object_keys(
+                    description,
+                    &["center", "axis", "major_radius", "minor_radius", "material"],
+                )?;
+                let major_radius = positive_number(field(description, "major_radius")?)?;
+                let minor_radius = positive_number(field(description, "minor_radius")?)?;
+                if minor_radius >= major_radius {
+                    return Err(JsonError::new(
+                        description.line(),
+                        String::from("torus minor radius must be below its major radius"),
+                    )
+                    .into());
+                }
+                std::sync::Arc::new(Torus::new(
+                    vec3(field(description, "center")?)?,
+                    optional(description, "axis", direction)?
+                        .unwrap_or_else(|| Vec3::new(0.0, 1.0, 0.0)),
+                    major_radius,
+                    minor_radius,
+                    material("material")?,
+                ))
+            }
+            "constant_medium" => {
+                check_object_keys(description, &["boundary", "density", "material"])?;
+                std::sync::Arc::new(ConstantMedium::new(
+                    self.object(field(description, "boundary")?, materials)?,
+                    positive_number(field(description, "density")?)?,
+                    material("material")?,
+                ))
+            }
+            "mesh" => {
+                check_object_keys(description, &["path", "material"])?;
+                let path = self.directory.join(string(field(description, "path")?)?);
+                let default_material = match description.get("material") {
+                    Some(_) => material("material")?,
+                    None => std::sync::Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))),
+                };
+                let material_name = description
+                    .get("material")
+                    .map(|name| string(name).map(String::from))
+                    .transpose()?;
+                let key = (path, material_name);
+                let mesh = self.meshes.borrow().get(&key).cloned();
+                match mesh {
+                    Some(mesh) => mesh,
+                    None => {
+                        let mesh: std::sync::Arc<dyn Hittable> = std::sync::Arc::new(
+                            crate::obj::load(&key.0, default_material).map_err(BuildError::Obj)?,
+                        );
+                        self.meshes.borrow_mut().insert(key, mesh.clone());
+                        mesh
+                    }
+                }
+            }
+            _ => {
+                return Err(JsonError::new(
+                    field(description, "type")?.line(),
+                    format!("unknown object type '{}'", kind),
+                )
+                .into())
+            }
+        };
+
+        let object: std::sync::Arc<dyn Hittable> = match description.get("transform") {
+            Some(transform) => {
+                std::sync::Arc::new(Transformed::new(object, self::transform(transform)?))
+            }
+            None => object,
+        };
+
+        match description.get("motion") {
+            Some(motion) => {
+                check_keys(motion, &["offset", "time_0", "time_1"])?;
+                let (time_0, time_1) = time_interval(motion)?;
+                Ok(std::sync::Arc::new(Moving::new(
+                    object,
+                    Vec3::new(0.0, 0.0, 0.0),
+                    vec3(field(motion, "offset")?)?,
+                    time_0,
+                    time_1,
+                )))
+            }
+            None => Ok(object),
+        }
+    }
+}
+
+// Emissive objects that can be sampled directly. Transformed, moving and mesh lights are still
+// rendered, but are only found by scattered rays.
+fn is_light(description: &JsonValue, emissive: &HashSet<&str>) -> Result<bool, JsonError> {
+    let kind = string(field(description, "type")?)?;
+    if !matches!(
+        kind,
+        "sphere" | "triangle" | "quad" | "rectangle" | "disk" | "annulus"
+    ) || description.get("transform").is_some()
+        || description.get("motion").is_some()
+    {
+        return Ok(false);
+    }
+
+    Ok(emissive.contains(string(field(description, "material")?)?))
+}
+
+fn type_error(value: &JsonValue, expected: &str) -> JsonError {
+    JsonError::new(
+        value.line(),
+        format!("expected {}, found {}", expected, value.type_name()),
+    )
+}
+
+fn check_object_keys(value: &JsonValue, allowed: &[&str]) -> Result<(), JsonError> {
+    let common = ["type", "transform", "motion"];
+    let allowed: Vec<&str> = common.iter().chain(allowed).copied().collect();
+    check_keys(value, &allowed)
+}
+
+fn check_keys(value: &JsonValue, allowed: &[&str]) -> Result<(), JsonError> {
+    for (key, member) in object(value)? {
+        if !allowed.contains(&key.as_str()) {
+            return Err(JsonError::new(
+                member.line(),
+                format!("unknown field '{}'", key),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn field<'v>(value: &'v JsonValue, key: &str) -> Result<&'v JsonValue, JsonError> {
+    object(value)?;
+    value
+        .get(key)
+        .ok_or_else(|| JsonError::new(value.line(), format!("missing field '{}'", key)))
+}
+
+fn optional<'v, T, F>(value: &'v JsonValue, key: &str, convert: F) -> Result<Option<T>, JsonError>
+where
+    F: Fn(&'v JsonValue) -> Result<T, JsonError>,
+{
+    value.get(key).map(convert).transpose()
+}
+
+fn object(value: &JsonValue) -> Result<&[(String, JsonValue)], JsonError> {
+    match value.kind() {
+        JsonKind::Object(members) => Ok(members),
+        _ => Err(type_error(value, "an object")),
+    }
+}
+
+fn array(value: &JsonValue) -> Result<&[JsonValue], JsonError> {
+    match value.kind() {
+        JsonKind::Array(elements) => Ok(elements),
+        _ => Err(type_error(value, "an array")),
+    }
+}
+
+fn string(value: &JsonValue) -> Result<&str, JsonError> {
+    match value.kind() {
+        JsonKind::String(string) => Ok(string),
+        _ => Err(type_error(value, "a string")),
+    }
+}
+
+fn boolean(value: &JsonValue) -> Result<bool, JsonError> {
+    match value.kind() {
+        JsonKind::Bool(boolean) => Ok(*boolean),
+        _ => Err(type_error(value, "a boolean")),
+    }
+}
+
+fn number(value: &JsonValue) -> Result<f64, JsonError> {
+    match value.kind() {
+        JsonKind::Number(number) => Ok(*number),
+        _ => Err(type_error(value, "a number")),
+    }
+}
+
+fn positive_usize(value: &JsonValue) -> Result<usize, JsonError> {
+    let number = number(value)?;
+    if number < 1.0 || number.fract() != 0.0 {
+        return Err(JsonError::new(
+            value.line(),
+            format!("expected a positive integer, found {}", number),
+        ));
+    }
+    Ok(number as usize)
+}
+
+fn image_size(value: &JsonValue) -> Result<usize, JsonError> {
+    let size = positive_usize(value)?;
+    if size < 2 {
+        return Err(JsonError::new(
+            value.line(),
+            format!(
+                "expected an image size of at least 2 pixels, found {}",
+                size
+            ),
+        ));
+    }
+    Ok(size)
+}
+
+fn positive_number(value: &JsonValue) -> Result<f64, JsonError> {
+    let number = number(value)?;
+    if number <= 0.0 {
+        return Err(JsonError::new(
+            value.line(),
+            format!("expected a positive number, found {}", number),
+        ));
+    }
+    Ok(number)
+}
+
+fn seed(value: &JsonValue) -> Result<u64, JsonError> {
+    let number = number(value)?;
+    if number < 0.0 || number.fract() != 0.0 || number > u64::MAX as f64 {
+        return Err(JsonError::new(
+            value.line(),
+            format!("expected a non-negative integer seed, found {}", number),
+        ));
+    }
+    Ok(number as u64)
+}
+
+// The optional "time_0" and "time_1" of a moving object, defaulting to 0 and 1.
+fn time_interval(value: &JsonValue) -> Result<(f64, f64), JsonError> {
+    let time_0 = optional(value, "time_0", number)?.unwrap_or(0.0);
+    let time_1 = optional(value, "time_1", number)?.unwrap_or(1.0);
+    if time_0 >= time_1 {
+        return Err(JsonError::new(
+            value.line(),
+            format!("time_0 ({}) must be less than time_1 ({})", time_0, time_1),
+        ));
+    }
+    Ok((time_0, time_1))
+}
+
+fn numbers<const N: usize>(value: &JsonValue) -> Result<[f64; N], JsonError> {
+    let elements = array(value)?;
+    if elements.len() != N {
+        return Err(JsonError::new(
+            value.line(),
+            format!("expected {} numbers, found {}", N, elements.len()),
+        ));
+    }
+
+    let mut numbers = [0.0; N];
+    for (number, element) in numbers.iter_mut().zip(elements) {
+        *number = self::number(element)?;
+    }
+    Ok(numbers)
+}
+
+fn vec3(value: &JsonValue) -> Result<Vec3, JsonError> {
+    let [x, y, z] = numbers(value)?;
+    Ok(Vec3::new(x, y, z))
+}
+
+// A normal or axis, which needs a direction.
+fn direction(value: &JsonValue) -> Result<Vec3, JsonError> {
+    let direction = vec3(value)?;
+    if direction.near_zero() {
+        return Err(JsonError::new(
+            value.line(),
+            String::from("expected a nonzero vector"),
+        ));
+    }
+    Ok(direction)
+}
+
+fn color(value: &JsonValue) -> Result<Color, JsonError> {
+    let [r, g, b] = numbers(value)?;
+    Ok(Color::new(r, g, b))
+}
+
+fn texture(
+    value: &JsonValue,
+    textures: &HashMap<String, std::sync::Arc<dyn Texture>>,
+) -> Result<std::sync::Arc<dyn Texture>, JsonError> {
+    match value.kind() {
+        JsonKind::String(name) => textures
+            .get(name)
+            .cloned()
+            .ok_or_else(|| JsonError::new(value.line(), format!("unknown texture '{}'", name))),
+        _ => Ok(std::sync::Arc::new(SolidColor::new(color(value)?))),
+    }
+}
+
+fn transform(value: &JsonValue) -> Result<Transform, JsonError> {
+    let mut transform = Transform::identity();
+
+    for step in array(value)? {
+        let (operation, argument) = match object(step)? {
+            [(operation, argument)] => (operation.as_str(), argument),
+            _ => {
+                return Err(JsonError::new(
+                    step.line(),
+                    String::from("expected a single transform operation"),
+                ))
+            }
+        };
+
+        let next = match operation {
+            "translate" => Transform::translate(vec3(argument)?),
+            "scale" => {
+                let factors = match argument.kind() {
+                    JsonKind::Number(factor) => Vec3::new(*factor, *factor, *factor),
+                    _ => vec3(argument)?,
+                };
+                Transform::scale(factors).ok_or_else(|| {
+                    JsonError::new(
+                        argument.line(),
+                        String::from("scale factors must be nonzero"),
+                    )
+                })?
+            }
+            "rotate" => {
+                check_keys(argument, &["axis", "degrees"])?;
+                Transform::rotate(
+                    &vec3(field(argument, "axis")?)?,
+                    number(field(argument, "degrees")?)?,
+                )
+            }
+            "rotate_x" => Transform::rotate(&Vec3::new(1.0, 0.0, 0.0), number(argument)?),
+            "rotate_y" => Transform::rotate(&Vec3::new(0.0, 1.0, 0.0), number(argument)?),
+            "rotate_z" => Transform::rotate(&Vec3::new(0.0, 0.0, 1.0), number(argument)?),
+            "matrix" => {
+                let m: [f64; 16] = numbers(argument)?;
+                let matrix = Matrix4::new([
+                    [m[0], m[1], m[2], m[3]],
+                    [m[4], m[5], m[6], m[7]],
+                    [m[8], m[9], m[10], m[11]],
+                    [m[12], m[13], m[14], m[15]],
+                ]);
+                Transform::from_matrix(matrix).ok_or_else(|| {
+                    JsonError::new(argument.line(), String::from("matrix is not invertible"))
+                })?
+            }
+            _ => {
+                return Err(JsonError::new(
+                    step.line(),
+                    format!("unknown transform operation '{}'", operation),
+                ))
+            }
+        };
+
+        transform = transform.then(&next);
+    }
+
+    Ok(transform)
+}
+
+fn uv(value: &JsonValue) -> Result<(f64, f64), JsonError> {
+    let [u, v] = numbers(value)?;
+    Ok((u, v))
+}
+
+fn triple<T, F>(value: &JsonValue, convert: F) -> Result<[T; 3], JsonError>
+where
+    F: Fn(&JsonValue) -> Result<T, JsonError>,
+{
+    match array(value)? {
+        [a, b, c] => Ok([convert(a)?, convert(b)?, convert(c)?]),
+        elements => Err(JsonError::new(
+            value.line(),
+            format!("expected 3 elements, found {}", elements.len()),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The line and message of the error in a scene source.
+    fn error(source: &str) -> (usize, String) {
+        match Scene::parse(source, Path::new("scene.json")) {
+            Err(SceneError::Parse { line, message, .. }) => (line, message),
+            Err(error) => panic!("unexpected error: {}", error),
+            Ok(_) => panic!("the scene loaded"),
+        }
+    }
+
+    // A scene whose only object, on line 5, is `object`.
+    fn with_object(object: &str) -> String {
+        format!(
+            r#"{{
+  "camera": {{ "look_from": [0, 0, 1], "look_at": [0, 0, 0], "vertical_field_of_view": 40 }},
+  "materials": {{ "white": {{ "type": "lambertian", "albedo": [0.5, 0.5, 0.5] }} }},
+  "objects": [
+    {}
+  ]
+}}"#,
+            object
+        )
+    }
+
+    // A scene whose only object starts on line 5 and spans the lines of `members`.
+    fn with_members(members: &[&str]) -> String {
+        with_object(&format!("{{\n      {}\n    }}", members.join(",\n      ")))
+    }
+
+    #[test]
+    fn unknown_fields_report_their_line() {
+        let source = with_members(&[
+            r#""type": "sphere""#,
+            r#""center": [0, 0, 0]"#,
+            r#""radius": 1"#,
+            r#""colour": "white""#,
+        ]);
+        assert_eq!(error(&source), (9, String::from("unknown field 'colour'")));
+    }
+
+    #[test]
+    fn wrong_types_report_the_line_of_the_value() {
+        let source = with_members(&[
+            r#""type": "sphere""#,
+            r#""center": [0, 0, 0]"#,
+            r#""radius": "large""#,
+            r#""material": "white""#,
+        ]);
+        assert_eq!(
+            error(&source),
+            (8, String::from("expected a number, found string"))
+        );
+    }
+
+    #[test]
+    fn missing_fields_report_the_line_of_their_object() {
+        let source = with_members(&[
+            r#""type": "sphere""#,
+            r#""center": [0, 0, 0]"#,
+            r#""material": "white""#,
+        ]);
+        assert_eq!(error(&source), (5, String::from("missing field 'radius'")));
+    }
+
+    #[test]
+    fn syntax_errors_report_their_line() {
+        let source = with_members(&[
+            r#""type": "sphere""#,
+            r#""center": [0, 0 0]"#,
+            r#""radius": 1"#,
+        ]);
+        assert_eq!(
+            error(&source),
+            (7, String::from("expected ',' or ']' in array"))
+        );
+    }
+
+    #[test]
+    fn images_are_at_least_two_pixels_wide_and_high() {
+        let source = r#"{
+  "render": {
+    "width": 1,
+    "height": 2
+  },
+  "camera": { "look_from": [0, 0, 1], "look_at": [0, 0, 0], "vertical_field_of_view": 40 },
+  "objects": []
+}"#;
+        assert_eq!(
+            error(source),
+            (
+                3,
+                String::from("expected an image size of at least 2 pixels, found 1")
+            )
+        );
+
+        let settings = RenderSettings::new(1, 0, 1, 1);
+        assert_eq!((settings.width(), settings.height()), (2, 2));
+    }
+
+    #[test]
+    fn zero_normals_and_axes_are_rejected() {
+        for object in &[
+            r#"{ "type": "disk", "center": [0, 0, 0], "normal": [0, 0, 0], "radius": 1, "material": "white" }"#,
+            r#"{ "type": "annulus", "center": [0, 0, 0], "normal": [0, 0, 0], "inner_radius": 0.5, "outer_radius": 1, "material": "white" }"#,
+            r#"{ "type": "torus", "center": [0, 0, 0], "axis": [0, 0, 0], "major_radius": 1, "minor_radius": 0.5, "material": "white" }"#,
+        ] {
+            assert_eq!(
+                error(&with_object(object)),
+                (5, String::from("expected a nonzero vector"))
+            );
+        }
+    }
+
+    #[test]
+    fn spindle_tori_are_rejected() {
+        let object = r#"{ "type": "torus", "center": [0, 0, 0], "major_radius": 1, "minor_radius": 1, "material": "white" }"#;
+        assert_eq!(
+            error(&with_object(object)),
+            (
+                5,
+                String::from("torus minor radius must be below its major radius")
+            )
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use crate::buffer::Buffer;
+use crate::color::Color;
+use crate::perlin::Perlin;
+use crate::pixel::Pixel;
+use crate::vec3::Vec3;
+use std::path::Path;
+
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, point: &Vec3) -> Color;
+}
+
+pub struct SolidColor {
+    color: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _point: &Vec3) -> Color {
+        self.color.clone()
+    }
+}
+
+pub struct CheckerTexture {
+    inverse_scale: f64,
+    even: std::sync::Arc<dyn Texture>,
+    odd: std::sync::Arc<dyn Texture>,
+}
+
+impl CheckerTexture {
+    pub fn new(
+        scale: f64,
+        even: std::sync::Arc<dyn Texture>,
+        odd: std::sync::Arc<dyn Texture>,
+    ) -> Self {
+        Self {
+            inverse_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, point: &Vec3) -> Color {
+        let x = (self.inverse_scale * point.x()).floor() as i64;
+        let y = (self.inverse_scale * point.y()).floor() as i64;
+        let z = (self.inverse_scale * point.z()).floor() as i64;
+
+        if (x + y + z).rem_euclid(2) == 0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum WrapMode {
+    Repeat,
+    Mirror,
+    Clamp,
+}
+
+impl WrapMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "repeat" => Some(WrapMode::Repeat),
+            "mirror" => Some(WrapMode::Mirror),
+            "clamp" => Some(WrapMode::Clamp),
+            _ => None,
+        }
+    }
+
+    fn wrap(&self, index: i64, size: usize) -> usize {
+        let size = size as i64;
+        let wrapped = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::Mirror => {
+                let period = index.rem_euclid(2 * size);
+                if period < size {
+                    period
+                } else {
+                    2 * size - 1 - period
+                }
+            }
+            WrapMode::Clamp => index.clamp(0, size - 1),
+        };
+        wrapped as usize
+    }
+}
+
+pub struct ImageTexture {
+    image: Buffer,
+    wrap_mode: WrapMode,
+}
+
+impl ImageTexture {
+    pub fn new(image: Buffer, wrap_mode: WrapMode) -> Self {
+        Self { image, wrap_mode }
+    }
+
+    pub fn load(path: &Path, wrap_mode: WrapMode) -> std::io::Result<Self> {
+        let image = crate::image::read_image(path)?;
+        if image.width() == 0 || image.height() == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} is empty", path.display()),
+            ));
+        }
+        Ok(Self::new(image, wrap_mode))
+    }
+
+    fn texel(&self, i: i64, j: i64) -> &Color {
+        let i = self.wrap_mode.wrap(i, self.image.width());
+        let j = self.wrap_mode.wrap(j, self.image.height());
+        self.image.get(&Pixel::new(i, j))
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _point: &Vec3) -> Color {
+        let x = u * self.image.width() as f64 - 0.5;
+        let y = v * self.image.height() as f64 - 0.5;
+        let (i, j) = (x.floor(), y.floor());
+        let (s, t) = (x - i, y - j);
+        let (i, j) = (i as i64, j as i64);
+
+        (1.0 - t) * ((1.0 - s) * self.texel(i, j) + s * self.texel(i + 1, j))
+            + t * ((1.0 - s) * self.texel(i, j + 1) + s * self.texel(i + 1, j + 1))
+    }
+}
+
+pub struct NoiseTexture {
+    noise: Perlin,
+    scale: f64,
+    octaves: usize,
+}
+
+impl NoiseTexture {
+    pub fn new(seed: u64, scale: f64, octaves: usize) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            octaves,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, point: &Vec3) -> Color {
+        let noise = self.noise.fbm(&(self.scale * point), self.octaves);
+        Color::new(1.0, 1.0, 1.0) * (0.5 * (1.0 + noise)).clamp(0.0, 1.0)
+    }
+}
+
+pub struct MarbleTexture {
+    noise: Perlin,
+    scale: f64,
+    color: Color,
+}
+
+impl MarbleTexture {
+    pub fn new(seed: u64, scale: f64, color: Color) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            color,
+        }
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, _u: f64, _v: f64, point: &Vec3) -> Color {
+        let phase = self.scale * point.z() + 10.0 * self.noise.turbulence(point, 7);
+        &self.color * (0.5 * (1.0 + phase.sin()))
+    }
+}
+
+pub struct WoodTexture {
+    noise: Perlin,
+    scale: f64,
+    light: Color,
+    dark: Color,
+}
+
+impl WoodTexture {
+    pub fn new(seed: u64, scale: f64, light: Color, dark: Color) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            light,
+            dark,
+        }
+    }
+}
+
+impl Texture for WoodTexture {
+    fn value(&self, _u: f64, _v: f64, point: &Vec3) -> Color {
+        let point = self.scale * point;
+        let radius = (point.x().powi(2) + point.z().powi(2)).sqrt();
+        let rings = radius + 0.5 * self.noise.turbulence(&point, 4);
+        let t = (rings - rings.floor()).powi(2);
+
+        (1.0 - t) * &self.light + t * &self.dark
+    }
+}
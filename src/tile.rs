@@ -0,0 +1,61 @@
+use crate::pixel::Pixel;
+
+#[derive(Clone)]
+pub struct Tile {
+    i: usize,
+    j: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Tile {
+    pub fn new(i: usize, j: usize, width: usize, height: usize) -> Self {
+        Self {
+            i,
+            j,
+            width,
+            height,
+        }
+    }
+
+    pub fn split(image_width: usize, image_height: usize, tile_size: usize) -> Vec<Tile> {
+        let mut tiles = Vec::new();
+        for j in (0..image_height).step_by(tile_size) {
+            for i in (0..image_width).step_by(tile_size) {
+                tiles.push(Tile::new(
+                    i,
+                    j,
+                    tile_size.min(image_width - i),
+                    tile_size.min(image_height - j),
+                ));
+            }
+        }
+        tiles
+    }
+
+    pub fn i(&self) -> usize {
+        self.i
+    }
+
+    pub fn j(&self) -> usize {
+        self.j
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn area(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn pixels(&self) -> impl Iterator<Item = (Pixel, Pixel)> + '_ {
+        (0..self.height).flat_map(move |j| {
+            (0..self.width).map(move |i| (Pixel::new(i, j), Pixel::new(self.i + i, self.j + j)))
+        })
+    }
+}
@@ -1,4 +1,4 @@
-use crate::number::random_f64;
+use crate::number::Pcg32;
 
 #[derive(Clone, Copy)]
 pub struct Vec3 {
@@ -48,29 +48,29 @@ impl Vec3 {
         self.x() * v.x() + self.y() * v.y() + self.z() * v.z()
     }
 
-    pub fn random(min_max: Option<(f64, f64)>) -> Self {
+    pub fn random(min_max: Option<(f64, f64)>, rng: &mut Pcg32) -> Self {
         Self::new(
-            random_f64(min_max),
-            random_f64(min_max),
-            random_f64(min_max),
+            rng.random_f64(min_max),
+            rng.random_f64(min_max),
+            rng.random_f64(min_max),
         )
     }
 
-    pub fn random_in_unit_sphere() -> Self {
+    pub fn random_in_unit_sphere(rng: &mut Pcg32) -> Self {
         loop {
-            let point = Self::random(Some((-1.0, 1.0)));
+            let point = Self::random(Some((-1.0, 1.0)), rng);
             if point.length_squared() < 1.0 {
                 return point;
             }
         }
     }
 
-    pub fn random_unit_vector() -> Self {
-        Self::random_in_unit_sphere().unit_vector()
+    pub fn random_unit_vector(rng: &mut Pcg32) -> Self {
+        Self::random_in_unit_sphere(rng).unit_vector()
     }
 
-    pub fn random_in_unit_hemisphere(normal: &Self) -> Self {
-        let in_unit_sphere = Self::random_in_unit_sphere();
+    pub fn random_in_unit_hemisphere(normal: &Self, rng: &mut Pcg32) -> Self {
+        let in_unit_sphere = Self::random_in_unit_sphere(rng);
         if in_unit_sphere.dot(normal) > 0.0 {
             in_unit_sphere
         } else {
@@ -78,11 +78,11 @@ impl Vec3 {
         }
     }
 
-    pub fn random_in_unit_disk() -> Self {
+    pub fn random_in_unit_disk(rng: &mut Pcg32) -> Self {
         loop {
             let point = Self::new(
-                random_f64(Some((-1.0, 1.0))),
-                random_f64(Some((-1.0, 1.0))),
+                rng.random_f64(Some((-1.0, 1.0))),
+                rng.random_f64(Some((-1.0, 1.0))),
                 0.0,
             );
             if point.length_squared() < 1.0 {
@@ -110,6 +110,19 @@ impl Vec3 {
     }
 }
 
+impl std::ops::Index<usize> for Vec3 {
+    type Output = f64;
+
+    fn index(&self, axis: usize) -> &Self::Output {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("axis {} out of range", axis),
+        }
+    }
+}
+
 impl std::ops::Add for &Vec3 {
     type Output = Vec3;
 
@@ -126,7 +139,7 @@ impl std::ops::Add<Vec3> for &Vec3 {
     type Output = Vec3;
 
     fn add(self, rhs: Vec3) -> Self::Output {
-        self + &rhs
+        Vec3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
     }
 }
 
@@ -134,7 +147,7 @@ impl std::ops::Add for Vec3 {
     type Output = Vec3;
 
     fn add(self, rhs: Self) -> Self::Output {
-        &self + &rhs
+        Vec3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
     }
 }
 
@@ -154,7 +167,7 @@ impl std::ops::Div<f64> for Vec3 {
     type Output = Vec3;
 
     fn div(self, rhs: f64) -> Self::Output {
-        &self / rhs
+        Vec3::new(self.x / rhs, self.y / rhs, self.z / rhs)
     }
 }
 
@@ -174,7 +187,7 @@ impl std::ops::Mul<f64> for Vec3 {
     type Output = Vec3;
 
     fn mul(self, rhs: f64) -> Self::Output {
-        &self * rhs
+        Vec3::new(self.x * rhs, self.y * rhs, self.z * rhs)
     }
 }
 
@@ -190,7 +203,7 @@ impl std::ops::Mul<Vec3> for f64 {
     type Output = Vec3;
 
     fn mul(self, rhs: Vec3) -> Self::Output {
-        &rhs * self
+        Vec3::new(self * rhs.x, self * rhs.y, self * rhs.z)
     }
 }
 
@@ -230,7 +243,7 @@ impl std::ops::Sub<Vec3> for &Vec3 {
     type Output = Vec3;
 
     fn sub(self, rhs: Vec3) -> Self::Output {
-        self - &rhs
+        Vec3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
     }
 }
 
@@ -238,7 +251,7 @@ impl std::ops::Sub for Vec3 {
     type Output = Vec3;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        &self - &rhs
+        Vec3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
     }
 }
 
@@ -246,6 +259,6 @@ impl std::ops::Sub<&Vec3> for Vec3 {
     type Output = Vec3;
 
     fn sub(self, rhs: &Vec3) -> Self::Output {
-        &self - rhs
+        Vec3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
     }
 }
@@ -0,0 +1,214 @@
+use std::convert::TryInto;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const WIDTH: usize = 64;
+const SAMPLES_PER_PIXEL: usize = 8;
+const SEED: u64 = 1;
+const TOLERANCE: f64 = 0.02;
+
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<[f32; 3]>,
+}
+
+impl Image {
+    fn read(path: &Path) -> Image {
+        let bytes = std::fs::read(path)
+            .unwrap_or_else(|error| panic!("failed to read {}: {}", path.display(), error));
+
+        let mut header = Vec::new();
+        let mut position = 0;
+        while header.len() < 4 {
+            let start = position;
+            while !bytes[position].is_ascii_whitespace() {
+                position += 1;
+            }
+            header.push(String::from_utf8_lossy(&bytes[start..position]).into_owned());
+            position += 1;
+        }
+        assert_eq!(header[0], "PF", "{} is not a color PFM", path.display());
+        assert_eq!(header[3], "-1.0", "{} is not little endian", path.display());
+
+        let width: usize = header[1].parse().unwrap();
+        let height: usize = header[2].parse().unwrap();
+        let pixels = bytes[position..]
+            .chunks_exact(12)
+            .map(|pixel| {
+                let channel =
+                    |k: usize| f32::from_le_bytes(pixel[4 * k..4 * k + 4].try_into().unwrap());
+                [channel(0), channel(1), channel(2)]
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pixels.len(),
+            width * height,
+            "{} is truncated",
+            path.display()
+        );
+
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn root_mean_square_error(&self, other: &Image) -> f64 {
+        let sum = self
+            .pixels
+            .iter()
+            .zip(&other.pixels)
+            .flat_map(|(a, b)| (0..3).map(move |k| encode(a[k]) - encode(b[k])))
+            .map(|difference| difference.powi(2))
+            .sum::<f64>();
+        (sum / (3 * self.pixels.len()) as f64).sqrt()
+    }
+
+    fn write_difference(&self, other: &Image, path: &Path) {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path).unwrap());
+        write!(file, "P6\n{} {}\n255\n", self.width, self.height).unwrap();
+        for j in (0..self.height).rev() {
+            for i in 0..self.width {
+                let a = self.pixels[j * self.width + i];
+                let b = other.pixels[j * self.width + i];
+                for k in 0..3 {
+                    let difference = 4.0 * (encode(a[k]) - encode(b[k])).abs();
+                    file.write_all(&[(255.0 * difference.min(1.0)) as u8])
+                        .unwrap();
+                }
+            }
+        }
+        file.flush().unwrap();
+    }
+}
+
+fn encode(linear: f32) -> f64 {
+    let linear = (linear as f64).clamp(0.0, 1.0);
+    if linear <= 0.0031308 {
+        12.92 * linear
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn render(scene: &str, output: &Path) {
+    let scene = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("scenes")
+        .join(format!("{}.json", scene));
+
+    let result = Command::new(env!("CARGO_BIN_EXE_ray-tracing-in-one-weekend"))
+        .arg(&scene)
+        .args(["--width", &WIDTH.to_string()])
+        .args(["--samples", &SAMPLES_PER_PIXEL.to_string()])
+        .args(["--seed", &SEED.to_string()])
+        .args(["--format", "pfm"])
+        .arg("--output")
+        .arg(output)
+        .output()
+        .unwrap();
+
+    assert!(
+        result.status.success(),
+        "failed to render {}: {}",
+        scene.display(),
+        String::from_utf8_lossy(&result.stderr)
+    );
+}
+
+fn golden(scene: &str) {
+    let reference = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.pfm", scene));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        render(scene, &reference);
+        return;
+    }
+
+    let directory = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&directory).unwrap();
+    let actual = directory.join(format!("{}.pfm", scene));
+    render(scene, &actual);
+
+    let expected = Image::read(&reference);
+    let actual = Image::read(&actual);
+    assert_eq!(
+        (actual.width, actual.height),
+        (expected.width, expected.height),
+        "{} rendered at the wrong size",
+        scene
+    );
+
+    let error = actual.root_mean_square_error(&expected);
+    if error > TOLERANCE {
+        let difference = directory.join(format!("{}-difference.ppm", scene));
+        actual.write_difference(&expected, &difference);
+        panic!(
+            "{} differs from {} with an RMSE of {:.4} (tolerance {}), see {}",
+            scene,
+            reference.display(),
+            error,
+            TOLERANCE,
+            difference.display()
+        );
+    }
+}
+
+#[test]
+fn three_spheres() {
+    golden("three_spheres");
+}
+
+#[test]
+fn cloud() {
+    golden("cloud");
+}
+
+#[test]
+fn cornell_box() {
+    golden("cornell_box");
+}
+
+#[test]
+fn cornell_boxes() {
+    golden("cornell_boxes");
+}
+
+#[test]
+fn plane() {
+    golden("plane");
+}
+
+#[test]
+fn quadrics() {
+    golden("quadrics");
+}
+
+#[test]
+fn cornell_smoke() {
+    golden("cornell_smoke");
+}
+
+#[test]
+fn textures() {
+    golden("textures");
+}
+
+#[test]
+fn noise() {
+    golden("noise");
+}
+
+#[test]
+fn motion_blur() {
+    golden("motion_blur");
+}
+
+#[test]
+fn instances() {
+    golden("instances");
+}